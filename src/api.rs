@@ -1,41 +1,902 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use rand::distr::weighted::WeightedIndex;
+
 use crate::table;
 
+/// Strict, all-or-nothing counterpart to `parse_tables_lenient`.
 pub fn parse_tables(toml_str: &str) -> Result<Vec<table::Table>, toml::de::Error> {
     let list: table::TableList = toml::from_str(toml_str)?;
     Ok(list.table)
 }
 
+/// Parses tables written as JSON, e.g. `{"table": [{"name": ..., "dice": ..., "rows": [...]}]}`,
+/// with the same fields as in TOML.
+pub fn parse_tables_json(json_str: &str) -> Result<Vec<table::Table>, serde_json::Error> {
+    let list: table::TableList = serde_json::from_str(json_str)?;
+    Ok(list.table)
+}
+
+/// Parses each `[[table]]` on its own so one malformed table doesn't discard the rest.
+/// Returns the tables that parsed along with the index and error of each one that didn't.
+/// Only a syntax error in the document as a whole is returned as `Err`.
+#[allow(clippy::type_complexity)]
+pub fn parse_tables_lenient(
+    toml_str: &str,
+) -> Result<(Vec<table::Table>, Vec<(usize, toml::de::Error)>), toml::de::Error> {
+    let mut doc: toml::Table = toml::from_str(toml_str)?;
+
+    let raw_tables = match doc.remove("table") {
+        Some(toml::Value::Array(raw_tables)) => raw_tables,
+        Some(_) => {
+            return Err(serde::de::Error::custom("`table` must be an array of tables"));
+        }
+        None => Vec::new(),
+    };
+
+    let mut tables = Vec::new();
+    let mut errors = Vec::new();
+    for (index, raw) in raw_tables.into_iter().enumerate() {
+        match raw.try_into::<table::Table>() {
+            Ok(table) => tables.push(table),
+            Err(e) => errors.push((index, e)),
+        }
+    }
+
+    Ok((tables, errors))
+}
+
+/// Reads the files listed in `include = [...]` in `toml_str`, relative to the directory of
+/// `path` (the file `toml_str` came from), and in turn the files those include.
+/// Returns each included file's path and contents in the order they're reached, along with an
+/// error for each include that couldn't be read or that would include a file into itself.
+pub fn read_includes(path: &Path, toml_str: &str) -> (Vec<(PathBuf, String)>, Vec<String>) {
+    let mut included = Vec::new();
+    let mut errors = Vec::new();
+    let mut stack = vec![canonical(path)];
+    read_includes_into(path, toml_str, &mut stack, &mut included, &mut errors);
+    (included, errors)
+}
+
+fn read_includes_into(
+    path: &Path,
+    toml_str: &str,
+    stack: &mut Vec<PathBuf>,
+    included: &mut Vec<(PathBuf, String)>,
+    errors: &mut Vec<String>,
+) {
+    // A document that doesn't parse is reported when its tables are loaded.
+    let Ok(doc) = toml::from_str::<toml::Table>(toml_str) else {
+        return;
+    };
+    let includes = match doc.get("include") {
+        Some(toml::Value::Array(includes)) => includes,
+        Some(_) => {
+            errors.push(format!("{}: `include` must be a list of paths", path.display()));
+            return;
+        }
+        None => return,
+    };
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for include in includes {
+        let Some(name) = include.as_str() else {
+            errors.push(format!("{}: include {} isn't a path", path.display(), include));
+            continue;
+        };
+        let include_path = dir.join(name);
+        let key = canonical(&include_path);
+        if stack.contains(&key) {
+            errors.push(format!(
+                "{}: including {} would form a cycle",
+                path.display(),
+                include_path.display()
+            ));
+            continue;
+        }
+
+        match std::fs::read_to_string(&include_path) {
+            Ok(source) => {
+                stack.push(key);
+                included.push((include_path.clone(), source.clone()));
+                read_includes_into(&include_path, &source, stack, included, errors);
+                stack.pop();
+            }
+            Err(e) => errors.push(format!(
+                "{}: failed to include {}: {}",
+                path.display(),
+                include_path.display(),
+                e
+            )),
+        }
+    }
+}
+
+// Files are compared by canonical path so `a.toml` and `./a.toml` are the same include.
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Parses a time of day such as "06:00" into minutes past midnight.
+pub fn parse_time_of_day(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.split_once(':')?;
+    let hours: u32 = hours.parse().ok()?;
+    let minutes: u32 = minutes.parse().ok()?;
+    if hours >= 24 || minutes >= 60 {
+        return None;
+    }
+    Some(hours * 60 + minutes)
+}
+
+/// Default labels for parts of the day, by the time each one starts.
+pub const DEFAULT_TIME_BANDS: [(u32, &str); 5] = [
+    (5 * 60, "dawn"),
+    (8 * 60, "morning"),
+    (12 * 60, "afternoon"),
+    (17 * 60, "evening"),
+    (21 * 60, "night"),
+];
+
+/// The label of the band `minutes` falls in. Each band runs from its start until the next
+/// band starts; before the earliest start, the latest band carries over from the day before.
+pub fn time_band<'a>(
+    bands: impl IntoIterator<Item = (u32, &'a str)>,
+    minutes: u32,
+) -> Option<&'a str> {
+    let since_midnight = minutes % MINUTES_PER_DAY;
+    let mut bands: Vec<(u32, &str)> = bands.into_iter().collect();
+    bands.sort();
+    bands
+        .iter()
+        .rev()
+        .find(|(start, _)| *start <= since_midnight)
+        .or(bands.last())
+        .map(|(_, label)| *label)
+}
+
+/// Minutes to go forward from `now` to reach `time_of_day`. Arriving exactly at `now`
+/// counts as the next day's occurrence, so the clock always moves.
+pub fn minutes_until(now: u32, time_of_day: u32) -> u32 {
+    let since_midnight = now % MINUTES_PER_DAY;
+    match (time_of_day + MINUTES_PER_DAY - since_midnight) % MINUTES_PER_DAY {
+        0 => MINUTES_PER_DAY,
+        minutes => minutes,
+    }
+}
+
+/// Upper bound on the number of dice in a single roll, so a typo can't freeze the UI.
+pub const MAX_DICE: u32 = 10_000;
+
+/// Upper bound on the dice rolled in all for `roll_stats`, e.g. 1,000,000 rolls of 10d6.
+pub const MAX_STATS_DICE: u64 = 10_000_000;
+
+/// Most faces a loaded die can have, since each face's weight is kept.
+const MAX_WEIGHTED_SIDES: u32 = 1_000;
+
+/// Every dice notation `DiceSpec::parse` and friends accept, as (form, example, description), for
+/// `dice help`. Keep this in step with the parser; a test rolls each example.
+pub const DICE_NOTATIONS: &[(&str, &str, &str)] = &[
+    ("<count>d<sides>", "2d6", "Roll count dice and add them up"),
+    ("d<sides>", "d20", "Roll a single die"),
+    ("d66, d666", "d66", "Read each d6 as a digit, giving 11-66 or 111-666"),
+    ("w[<face>:<weight>,...]<dice>", "w[1:3,6:1]d6", "Loaded dice; unlisted faces weigh 1"),
+    (WEIGHTED_DICE, WEIGHTED_DICE, "As a table's dice, pick rows by their weight"),
+];
+
+/// Dice notation taken apart, e.g. `2d6+1` is two six-sided dice plus one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiceSpec {
+    pub count: u32,
+    pub sides: u32,
+    pub modifier: i64, // Added to the total, e.g. the `+1` of `1d8+1`
+    pub digits: bool,  // `d66`/`d666`: each d6 is a digit of the result rather than summed
+    pub weights: Vec<u32>, // Each face's weight, from 1, for loaded dice; empty for fair ones
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiceError {
+    NotDice(String),
+    TooManyDice(u32),
+    BadWeights { dice: String, reason: String }, // A loaded die's `w[...]` that doesn't parse
+}
+
+impl std::fmt::Display for DiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DiceError::NotDice(dice) => write!(f, "'{}' isn't dice notation", dice),
+            DiceError::TooManyDice(count) => {
+                write!(f, "{} dice is more than {} per roll", count, MAX_DICE)
+            }
+            DiceError::BadWeights { dice, reason } => {
+                write!(f, "'{}' has bad face weights: {}", dice, reason)
+            }
+        }
+    }
+}
+
+impl DiceSpec {
+    /// Parses notation like "2d6", "d20", "d66" or "1d8+1". A missing count ("d20") means
+    /// one die. A `w[1:3,6:2]` prefix loads the dice: face 1 weighs 3, face 6 weighs 2 and the
+    /// rest weigh 1.
+    pub fn parse(dice: &str) -> Result<DiceSpec, DiceError> {
+        if let Some(loaded) = dice.strip_prefix("w[") {
+            let bad = |reason: String| DiceError::BadWeights { dice: dice.to_string(), reason };
+            let (weights, rest) = loaded
+                .split_once(']')
+                .ok_or_else(|| bad("no closing ']'".to_string()))?;
+            let mut spec = DiceSpec::parse(rest).map_err(|e| match e {
+                DiceError::NotDice(_) => DiceError::NotDice(dice.to_string()),
+                e => e,
+            })?;
+            if !spec.weights.is_empty() {
+                return Err(bad("weights are given twice".to_string()));
+            }
+            spec.weights = parse_face_weights(weights, spec.sides).map_err(bad)?;
+            return Ok(spec);
+        }
+
+        let not_dice = || DiceError::NotDice(dice.to_string());
+        let (base, modifier) = match dice.rfind(['+', '-']) {
+            Some(at) if at > 0 => (&dice[..at], dice[at..].parse().map_err(|_| not_dice())?),
+            _ => (dice, 0),
+        };
+        if let Some(digits) = digit_dice(base) {
+            let weights = Vec::new();
+            return Ok(DiceSpec { count: digits, sides: 6, modifier, digits: true, weights });
+        }
+
+        let (count, sides) = base.split_once('d').ok_or_else(not_dice)?;
+        let count = if count.is_empty() { 1 } else { count.parse().map_err(|_| not_dice())? };
+        let sides = sides.parse().map_err(|_| not_dice())?;
+        if sides == 0 {
+            return Err(not_dice());
+        }
+        Ok(DiceSpec { count, sides, modifier, digits: false, weights: Vec::new() })
+    }
+
+    /// Rolls each die, in order. Fails rather than rolling more than `MAX_DICE` dice.
+    pub fn roll<R: rand::Rng + ?Sized>(&self, rng: &mut R) -> Result<Vec<u32>, DiceError> {
+        if self.count > MAX_DICE {
+            return Err(DiceError::TooManyDice(self.count));
+        }
+        if self.weights.is_empty() {
+            return Ok((0..self.count).map(|_| rng.random_range(1..=self.sides)).collect());
+        }
+        // `parse` makes sure at least one face weighs something.
+        let faces = WeightedIndex::new(&self.weights).expect("loaded dice have a face to roll");
+        Ok((0..self.count).map(|_| rng.sample(&faces) as u32 + 1).collect())
+    }
+
+    /// The chance of each face coming up, from face 1.
+    fn face_probabilities(&self) -> Vec<f64> {
+        if self.weights.is_empty() {
+            return vec![1.0 / self.sides as f64; self.sides as usize];
+        }
+        let total: u64 = self.weights.iter().map(|weight| *weight as u64).sum();
+        self.weights.iter().map(|weight| *weight as f64 / total as f64).collect()
+    }
+
+    /// Combines dice rolled for this spec into its result: their digits for `d66`/`d666`,
+    /// their sum otherwise, plus the modifier.
+    pub fn total(&self, rolls: &[u32]) -> i64 {
+        let combined = if self.digits {
+            rolls.iter().fold(0, |result, roll| result * 10 + roll)
+        } else {
+            rolls.iter().fold(0u32, |total, roll| total.saturating_add(*roll))
+        };
+        combined as i64 + self.modifier
+    }
+}
+
+/// The weight of each face of a `sides`-sided die from the inside of `w[1:3,6:2]`, where
+/// faces not listed weigh 1. Fails with the reason if the weights can't be used.
+fn parse_face_weights(weights: &str, sides: u32) -> Result<Vec<u32>, String> {
+    if sides > MAX_WEIGHTED_SIDES {
+        return Err(format!("at most {} faces can be weighted", MAX_WEIGHTED_SIDES));
+    }
+    let mut parsed = vec![1; sides as usize];
+    let mut listed = Vec::new();
+    for pair in weights.split(',').map(str::trim) {
+        let (face, weight) = pair
+            .split_once(':')
+            .and_then(|(face, weight)| Some((face.trim().parse::<u32>().ok()?, weight)))
+            .and_then(|(face, weight)| Some((face, weight.trim().parse::<u32>().ok()?)))
+            .ok_or_else(|| format!("'{}' isn't <face>:<weight>", pair))?;
+        if !(1..=sides).contains(&face) {
+            return Err(format!("there's no face {} on a d{}", face, sides));
+        }
+        if listed.contains(&face) {
+            return Err(format!("face {} is weighted twice", face));
+        }
+        listed.push(face);
+        parsed[face as usize - 1] = weight;
+    }
+    if parsed.iter().all(|weight| *weight == 0) {
+        return Err("every face weighs 0".to_string());
+    }
+    Ok(parsed)
+}
+
+/// `dice` as a `DiceSpec` without a modifier, the only kind tables and `roll` accept.
+fn plain_dice(dice: &str) -> Option<DiceSpec> {
+    DiceSpec::parse(dice).ok().filter(|spec| spec.modifier == 0)
+}
+
+/// For `d66` and `d666`, the number of d6 read as the digits of the result (tens and units,
+/// hundreds too for `d666`) rather than summed.
+pub fn digit_dice(dice: &str) -> Option<u32> {
+    let sixes = dice.strip_prefix('d')?;
+    match sixes {
+        "66" | "666" => Some(sixes.len() as u32),
+        _ => None,
+    }
+}
+
+/// `dice` written the same way as any equivalent notation, e.g. "d20" and "1D20" both as
+/// "1d20", so tables' dice can be compared. Anything else is just lowercased.
+pub fn normalize_dice(dice: &str) -> String {
+    let dice = dice.to_lowercase();
+    match DiceSpec::parse(&dice) {
+        Ok(spec) if !spec.weights.is_empty() => dice,
+        Ok(spec) if spec.digits => format!("d{}", "6".repeat(spec.count as usize)),
+        Ok(spec) if spec.modifier != 0 => {
+            format!("{}d{}{:+}", spec.count, spec.sides, spec.modifier)
+        }
+        Ok(spec) => format!("{}d{}", spec.count, spec.sides),
+        Err(_) => dice,
+    }
+}
+
+/// Whether `s` is well-formed dice notation, as opposed to e.g. a table name.
+pub fn is_dice_notation(s: &str) -> bool {
+    plain_dice(s).is_some()
+}
+
+/// The lowest and highest totals `dice` can roll.
+pub fn dice_range(dice: &str) -> Option<std::ops::RangeInclusive<u32>> {
+    let spec = plain_dice(dice)?;
+    if spec.digits {
+        let ones = (10u32.pow(spec.count) - 1) / 9; // 11 or 111
+        return Some(ones..=ones * 6);
+    }
+    Some(spec.count..=spec.count.saturating_mul(spec.sides))
+}
+
+/// Entries of `table` that its own dice can never roll, because none of their numbers are in
+/// range. Tables with unparseable dice have nothing to compare against and report none.
+pub fn unreachable_entries(table: &table::Table) -> Vec<&table::Entry> {
+    let Some(range) = dice_range(&table.dice) else {
+        return Vec::new();
+    };
+    table
+        .rows
+        .iter()
+        .filter(|entry| entry.enabled && !reachable_in(entry, &range))
+        .collect()
+}
+
+/// Whether any of `entry`'s numbers, or any part of its range, falls in `range`. Doesn't look
+/// at whether it's enabled.
+pub fn reachable_in(entry: &table::Entry, range: &std::ops::RangeInclusive<u32>) -> bool {
+    let in_range = entry.range().is_some_and(|own| {
+        !own.is_empty() && own.start() <= range.end() && range.start() <= own.end()
+    });
+    in_range || entry.numbers.iter().any(|n| range.contains(n))
+}
+
+/// Gives each row of a dice table that has a `weight` but no `numbers`, `min` or `max` a run
+/// of results as long as its weight, in row order from the lowest roll, e.g. weights 50/30/20
+/// on d100 become 1-50, 51-80 and 81-100. Returns a warning if those weights don't add up to
+/// the number of results the dice can roll.
+pub fn assign_weight_ranges(table: &mut table::Table) -> Option<String> {
+    if is_weighted(&table.dice) || digit_dice(&table.dice).is_some() {
+        return None;
+    }
+    let range = dice_range(&table.dice)?;
+    let mut next = *range.start();
+    let mut assigned = false;
+    for entry in table.rows.iter_mut() {
+        let Some(weight) = entry.weight else {
+            continue;
+        };
+        if weight == 0 || !entry.numbers.is_empty() || entry.range().is_some() {
+            continue;
+        }
+        entry.min = Some(next);
+        entry.max = Some(next.saturating_add(weight - 1));
+        next = next.saturating_add(weight);
+        assigned = true;
+    }
+
+    let results = range.end() - range.start() + 1;
+    let total = next - range.start();
+    (assigned && total != results).then(|| {
+        format!("weights add up to {} but {} has {} results", total, table.dice, results)
+    })
+}
+
+/// `numbers`, in ascending order, with consecutive runs collapsed, e.g. "1, 3-5".
+pub fn format_runs(numbers: impl IntoIterator<Item = u32>) -> String {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for n in numbers {
+        match runs.last_mut() {
+            Some((_, end)) if end.checked_add(1) == Some(n) => *end = n,
+            _ => runs.push((n, n)),
+        }
+    }
+    runs.into_iter()
+        .map(|(start, end)| {
+            if start == end { start.to_string() } else { format!("{}-{}", start, end) }
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Everything that looks wrong with `table`, one problem per line: dice it can't roll, entries
+/// that can never come up or that overlap, `see`s of missing entries, and results no entry
+/// covers when there's no fallback.
+pub fn validate_table(table: &table::Table) -> Vec<String> {
+    if !is_weighted(&table.dice) && plain_dice(&table.dice).is_none() {
+        return vec![format!("'{}' isn't dice a table can roll", table.dice)];
+    }
+    let mut problems = Vec::new();
+    for entry in &table.rows {
+        if let (Some(min), Some(max)) = (entry.min, entry.max)
+            && min > max
+        {
+            problems.push(format!("entry '{}' has min {} above its max {}", entry.name, min, max));
+        }
+    }
+    for entry in unreachable_entries(table) {
+        // An inverted range has been reported already.
+        if entry.numbers.is_empty() && entry.range().is_some_and(|range| range.is_empty()) {
+            continue;
+        }
+        problems.push(format!("entry '{}' can never be rolled on {}", entry.name, table.dice));
+    }
+    for (first, second, total) in overlapping_entries(table) {
+        problems.push(format!(
+            "entries '{}' and '{}' both cover {}",
+            first.name, second.name, total
+        ));
+    }
+    for entry in &table.rows {
+        if let Some(see) = &entry.see
+            && table.entry_named(see).is_none()
+        {
+            problems.push(format!(
+                "entry '{}' sees '{}', which isn't in the table",
+                entry.name, see
+            ));
+        }
+    }
+    if table.fallback.is_none()
+        && !is_weighted(&table.dice)
+        && let Some(outcomes) = outcome_probabilities(&table.dice)
+    {
+        let gaps: Vec<u32> = outcomes
+            .into_iter()
+            .filter(|(total, p)| *p > 0.0 && find_entry(table, *total).is_none())
+            .map(|(total, _)| total)
+            .collect();
+        if !gaps.is_empty() {
+            problems.push(format!("no entry covers {}", format_runs(gaps)));
+        }
+    }
+    problems
+}
+
+/// Pairs of enabled entries that cover a result in common, by their numbers or `min`/`max`
+/// ranges, with the lowest such result. Rolling it gives whichever entry comes first. None for a
+/// `priority` table, whose entries are meant to overlap.
+pub fn overlapping_entries(table: &table::Table) -> Vec<(&table::Entry, &table::Entry, u32)> {
+    if table.priority {
+        return Vec::new();
+    }
+    let rows: Vec<&table::Entry> = table.rows.iter().filter(|entry| entry.enabled).collect();
+    let mut overlaps = Vec::new();
+    for (i, first) in rows.iter().enumerate() {
+        for second in &rows[i + 1..] {
+            let ranges_meet = match (first.range(), second.range()) {
+                (Some(a), Some(b)) => {
+                    Some((*a.start()).max(*b.start())).filter(|n| *n <= (*a.end()).min(*b.end()))
+                }
+                _ => None,
+            };
+            let shared = first
+                .numbers
+                .iter()
+                .chain(&second.numbers)
+                .copied()
+                .filter(|n| first.covers(*n) && second.covers(*n))
+                .chain(ranges_meet)
+                .min();
+            if let Some(total) = shared {
+                overlaps.push((*first, *second, total));
+            }
+        }
+    }
+    overlaps
+}
+
 pub fn roll(dice: &str) -> Option<u32> {
-    // Simple parser for dice notation like "2d6"
-    let parts: Vec<&str> = dice.split('d').collect();
-    if parts.len() != 2 {
+    roll_with(&mut rand::rng(), dice)
+}
+
+/// Like `roll`, but drawing from `rng`, so a seeded generator gives a reproducible result.
+pub fn roll_with(rng: &mut impl rand::Rng, dice: &str) -> Option<u32> {
+    let spec = plain_dice(dice)?;
+    let rolls = spec.roll(rng).ok()?;
+    Some(spec.total(&rolls) as u32)
+}
+
+/// Rolls dice with an optional flat modifier, like `1d8+1` or `2d6-2`, from `rng`.
+/// `None` if the dice part isn't valid notation.
+pub fn roll_modified_with(rng: &mut impl rand::Rng, expression: &str) -> Option<i64> {
+    let spec = DiceSpec::parse(expression).ok()?;
+    let rolls = spec.roll(rng).ok()?;
+    Some(spec.total(&rolls))
+}
+
+/// Rolls `dice` `times` times from `rng`, returning the mean, lowest and highest results.
+pub fn roll_stats(rng: &mut impl rand::Rng, dice: &str, times: u32) -> Option<(f64, u32, u32)> {
+    let spec = plain_dice(dice)?;
+    if times == 0 || spec.count as u64 * times as u64 > MAX_STATS_DICE {
         return None;
     }
-    let number_of_dice: u32 = parts[0].parse().ok()?;
-    let die_type: u32 = parts[1].parse().ok()?;
+    let (mut sum, mut min, mut max) = (0u64, u32::MAX, 0);
+    for _ in 0..times {
+        let roll = roll_with(rng, dice)?;
+        sum += roll as u64;
+        min = min.min(roll);
+        max = max.max(roll);
+    }
+    Some((sum as f64 / times as f64, min, max))
+}
+
+/// Rolls `dice` and returns each die's result, in the order rolled.
+pub fn roll_detailed(dice: &str) -> Option<Vec<u32>> {
+    roll_detailed_with(&mut rand::rng(), dice)
+}
 
-    let mut rng = rand::rng();
-    let mut total_roll = 0;
+fn roll_detailed_with(rng: &mut impl rand::Rng, dice: &str) -> Option<Vec<u32>> {
+    plain_dice(dice)?.roll(rng).ok()
+}
+
+/// Largest spread of totals `outcome_probabilities` will compute, to keep it cheap.
+const MAX_OUTCOMES: u32 = 1_000;
+
+/// The probability of each possible total of `dice`, lowest total first.
+/// Returns `None` for invalid dice or dice with too many possible totals.
+pub fn outcome_probabilities(dice: &str) -> Option<Vec<(u32, f64)>> {
+    let spec = plain_dice(dice)?;
+    let faces = spec.face_probabilities();
+    if spec.digits {
+        // Each digit 1-6 is one die's face, so a result's chance is that of its digits.
+        let outcomes = dice_range(dice)?
+            .filter(|n| n.to_string().chars().all(|c| ('1'..='6').contains(&c)))
+            .map(|n| {
+                let digits = n.to_string();
+                (n, digits.bytes().map(|digit| faces[(digit - b'1') as usize]).product())
+            })
+            .collect();
+        return Some(outcomes);
+    }
+    let (number_of_dice, die_type) = (spec.count, spec.sides);
+    if number_of_dice == 0 || number_of_dice.checked_mul(die_type)? > MAX_OUTCOMES {
+        return None;
+    }
 
+    // Distribution of the sum of the dice rolled so far, indexed by total - dice rolled.
+    let mut distribution = vec![1.0];
     for _ in 0..number_of_dice {
-        let roll: u32 = rand::Rng::random_range(&mut rng, 1..=die_type);
-        total_roll += roll;
+        let mut next = vec![0.0; distribution.len() + die_type as usize - 1];
+        for (total, p) in distribution.iter().enumerate() {
+            for (side, face) in faces.iter().enumerate() {
+                next[total + side] += p * face;
+            }
+        }
+        distribution = next;
     }
 
-    Some(total_roll)
+    Some(
+        distribution
+            .into_iter()
+            .enumerate()
+            .map(|(offset, p)| (offset as u32 + number_of_dice, p))
+            .collect(),
+    )
 }
 
-pub fn roll_on<'a>(table: &'a table::Table, dice: &str) -> (u32, Option<&'a table::Entry>) {
-    let total_roll = roll(dice).unwrap_or(0);
+/// A plain-language summary of how the results of `dice` are spread, e.g. which come up most
+/// and which rarely. `None` if `outcome_probabilities` can't handle the dice.
+pub fn describe_dice(dice: &str) -> Option<String> {
+    let outcomes = outcome_probabilities(dice)?;
+    let (lowest, _) = *outcomes.first()?;
+    let (highest, _) = *outcomes.last()?;
+    let most = outcomes.iter().map(|(_, p)| *p).fold(0.0, f64::max);
+    let least = outcomes.iter().map(|(_, p)| *p).fold(1.0, f64::min);
 
-    // Find the corresponding entry in the table
-    for entry in &table.rows {
-        if entry.numbers.contains(&total_roll) {
-            return (total_roll, Some(entry));
+    if most - least < 1e-9 {
+        return Some(format!(
+            "Rolling {}: every result from {} to {} is equally likely (about {} each).",
+            dice,
+            lowest,
+            highest,
+            percent(most)
+        ));
+    }
+
+    let totals_with = |p: f64| -> Vec<String> {
+        outcomes
+            .iter()
+            .filter(|(_, q)| (q - p).abs() < 1e-9)
+            .map(|(total, _)| total.to_string())
+            .collect()
+    };
+    Some(format!(
+        "Rolling {}: results cluster around {} (about {} each); extremes ({}) are rare \
+         (about {} each).",
+        dice,
+        totals_with(most).join(" and "),
+        percent(most),
+        totals_with(least).join(" and "),
+        percent(least)
+    ))
+}
+
+// Rounded to whole percents, or a decimal place for rare results.
+fn percent(p: f64) -> String {
+    if p < 0.01 {
+        format!("{:.1}%", p * 100.0)
+    } else {
+        format!("{:.0}%", p * 100.0)
+    }
+}
+
+/// The expected severity of a roll on `table`, treating its rows as severities 1..n in order,
+/// along with the probability that a roll lands on any row at all. Rolls that match no row are
+/// left out of the expectation.
+pub fn expected_severity(table: &table::Table, dice: &str) -> Option<(f64, f64)> {
+    if is_weighted(dice) {
+        let probabilities = weighted_probabilities(table);
+        let coverage: f64 = probabilities.iter().sum();
+        if coverage == 0.0 {
+            return None;
+        }
+        let weighted: f64 = probabilities
+            .iter()
+            .enumerate()
+            .map(|(index, p)| p * (index + 1) as f64)
+            .sum();
+        return Some((weighted / coverage, coverage));
+    }
+
+    let mut coverage = 0.0;
+    let mut weighted = 0.0;
+    for (total, p) in outcome_probabilities(dice)? {
+        if let Some(index) = entry_index(table, total) {
+            coverage += p;
+            weighted += p * (index + 1) as f64;
         }
     }
 
-    (total_roll, None)
+    if coverage == 0.0 {
+        return None;
+    }
+    Some((weighted / coverage, coverage))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    LessOrEqual,
+    GreaterOrEqual,
+    Less,
+    Greater,
+    Equal,
+}
+
+impl Comparison {
+    pub fn holds(self, roll: u32, threshold: u32) -> bool {
+        match self {
+            Comparison::LessOrEqual => roll <= threshold,
+            Comparison::GreaterOrEqual => roll >= threshold,
+            Comparison::Less => roll < threshold,
+            Comparison::Greater => roll > threshold,
+            Comparison::Equal => roll == threshold,
+        }
+    }
+}
+
+/// Splits a check such as "1d6<=2" into its dice, comparison and threshold.
+pub fn parse_check(check: &str) -> Option<(&str, Comparison, u32)> {
+    // Two-character operators first so "<=" isn't read as "<" followed by "=2".
+    const OPERATORS: [(&str, Comparison); 5] = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ];
+
+    let (dice, comparison, threshold) = OPERATORS.iter().find_map(|(op, comparison)| {
+        check
+            .split_once(op)
+            .map(|(dice, threshold)| (dice, *comparison, threshold))
+    })?;
+    if !is_dice_notation(dice) {
+        return None;
+    }
+    Some((dice, comparison, threshold.parse().ok()?))
+}
+
+/// The `dice` value that makes a table pick rows by their `weight` instead of by dice.
+pub const WEIGHTED_DICE: &str = "weighted";
+
+pub fn is_weighted(dice: &str) -> bool {
+    dice.eq_ignore_ascii_case(WEIGHTED_DICE)
+}
+
+/// Running totals of the rows' weights: row `i` is picked by a roll of
+/// `cumulative[i - 1] + 1 ..= cumulative[i]` on `1..=cumulative.last()`.
+pub fn cumulative_weights(table: &table::Table) -> Vec<u32> {
+    table
+        .rows
+        .iter()
+        .scan(0u32, |total, entry| {
+            *total = total.saturating_add(row_weight(entry));
+            Some(*total)
+        })
+        .collect()
+}
+
+/// A row's weight, which is 1 unless set and 0 for a disabled row.
+fn row_weight(entry: &table::Entry) -> u32 {
+    if entry.enabled { entry.weight.unwrap_or(1) } else { 0 }
+}
+
+/// Index of the row that `roll` lands on, given the table's cumulative weights.
+fn weighted_index(cumulative: &[u32], roll: u32) -> Option<usize> {
+    if roll == 0 {
+        return None;
+    }
+    let index = cumulative.partition_point(|&total| total < roll);
+    (index < cumulative.len()).then_some(index)
+}
+
+/// Each row's chance of being picked from a weighted table, in row order.
+pub fn weighted_probabilities(table: &table::Table) -> Vec<f64> {
+    let total = cumulative_weights(table).last().copied().unwrap_or(0);
+    table
+        .rows
+        .iter()
+        .map(|entry| {
+            if total == 0 {
+                0.0
+            } else {
+                row_weight(entry) as f64 / total as f64
+            }
+        })
+        .collect()
+}
+
+/// Each row's chance of coming up when `table` is rolled on its own dice, in row order. `None`
+/// if `outcome_probabilities` can't handle the dice.
+pub fn entry_probabilities(table: &table::Table) -> Option<Vec<f64>> {
+    if is_weighted(&table.dice) {
+        return Some(weighted_probabilities(table));
+    }
+    let mut probabilities = vec![0.0; table.rows.len()];
+    for (total, p) in outcome_probabilities(&table.dice)? {
+        if let Some(index) = entry_index(table, total) {
+            probabilities[index] += p;
+        }
+    }
+    Some(probabilities)
+}
+
+/// Rolls `dice` on `table`, returning the total, the individual dice that made it up and the
+/// matching entry. Weighted tables don't roll dice, so their breakdown is empty.
+pub fn roll_on<'a>(
+    table: &'a table::Table,
+    dice: &str,
+) -> (u32, Vec<u32>, Option<&'a table::Entry>) {
+    roll_on_with(&mut rand::rng(), table, dice)
+}
+
+/// Like `roll_on`, but drawing from `rng`.
+pub fn roll_on_with<'a>(
+    rng: &mut impl rand::Rng,
+    table: &'a table::Table,
+    dice: &str,
+) -> (u32, Vec<u32>, Option<&'a table::Entry>) {
+    if is_weighted(dice) {
+        let cumulative = cumulative_weights(table);
+        let total = cumulative.last().copied().unwrap_or(0);
+        if total == 0 {
+            return (0, Vec::new(), None);
+        }
+        let roll: u32 = rng.random_range(1..=total);
+        let entry = weighted_index(&cumulative, roll).map(|index| &table.rows[index]);
+        return (roll, Vec::new(), entry);
+    }
+
+    let Some(spec) = plain_dice(dice) else {
+        return (0, Vec::new(), None);
+    };
+    let rolls = spec.roll(rng).unwrap_or_default();
+    let total_roll = spec.total(&rolls) as u32;
+
+    (total_roll, rolls, find_entry(table, total_roll))
+}
+
+/// The row of `table` that rolling `total` gives: the first that covers it or, on a `priority`
+/// table, the one with the highest priority and then the fewest results. Disabled rows cover
+/// nothing.
+pub fn find_entry(table: &table::Table, total: u32) -> Option<&table::Entry> {
+    entry_index(table, total).map(|index| &table.rows[index])
+}
+
+fn entry_index(table: &table::Table, total: u32) -> Option<usize> {
+    let mut matches = table.rows.iter().enumerate().filter(|(_, entry)| covers(entry, total));
+    if !table.priority {
+        return matches.next().map(|(index, _)| index);
+    }
+    // `min_by_key` keeps the first of equals, so ties go to the row listed first.
+    matches
+        .min_by_key(|(_, entry)| (std::cmp::Reverse(entry.priority.unwrap_or(0)), entry.span()))
+        .map(|(index, _)| index)
+}
+
+fn covers(entry: &table::Entry, total: u32) -> bool {
+    entry.enabled && entry.covers(total)
+}
+
+/// Replaces each `{XdY}` in `text` with a roll of those dice, e.g. for entries like
+/// "{2d4} goblins". Braces holding anything else, or left unclosed, are kept as written and
+/// described in the returned warnings.
+pub fn interpolate(text: &str) -> (String, Vec<String>) {
+    let mut result = String::new();
+    let mut warnings = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            warnings.push(format!("unclosed '{{' in \"{}\"", text));
+            rest = &rest[start..];
+            break;
+        };
+        let braced = &rest[start..=start + len];
+        match roll(braced[1..braced.len() - 1].trim()) {
+            Some(value) => result.push_str(&value.to_string()),
+            None => {
+                warnings.push(format!("can't roll {} in \"{}\"", braced, text));
+                result.push_str(braced);
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    (result, warnings)
+}
+
+/// Rolls on `table` `times` times from `rng`, counting how often each result comes up. Rolls
+/// no row covers count towards the table's fallback, or `None` if it has none.
+pub fn tally(
+    rng: &mut impl rand::Rng,
+    table: &table::Table,
+    times: u32,
+) -> HashMap<Option<String>, u32> {
+    let mut counts = HashMap::new();
+    for _ in 0..times {
+        let (_, _, entry) = roll_on_with(rng, table, &table.dice);
+        let result = entry.map(|entry| entry.name.clone()).or_else(|| table.fallback.clone());
+        *counts.entry(result).or_insert(0) += 1;
+    }
+    counts
 }
 
 #[cfg(test)]
@@ -47,8 +908,729 @@ mod tests {
         let result = roll("2d6");
         assert!(result.is_some());
         let roll_value = result.unwrap();
-        assert!(roll_value >= 2 && roll_value <= 12);
+        assert!((2..=12).contains(&roll_value));
+    }
+    #[test]
+    fn test_dice_notation_examples() {
+        for (form, example, _) in DICE_NOTATIONS {
+            assert!(roll(example).is_some() || is_weighted(example), "{}", form);
+        }
+    }
+
+    #[test]
+    fn test_dice_spec_parse() {
+        let spec = |count, sides, modifier, digits| DiceSpec {
+            count,
+            sides,
+            modifier,
+            digits,
+            weights: Vec::new(),
+        };
+        assert_eq!(DiceSpec::parse("2d6"), Ok(spec(2, 6, 0, false)));
+        assert_eq!(DiceSpec::parse("d20"), Ok(spec(1, 20, 0, false)));
+        assert_eq!(DiceSpec::parse("d66"), Ok(spec(2, 6, 0, true)));
+        assert_eq!(DiceSpec::parse("d666"), Ok(spec(3, 6, 0, true)));
+        assert_eq!(DiceSpec::parse("1d66"), Ok(spec(1, 66, 0, false)));
+        assert_eq!(DiceSpec::parse("1d8+1"), Ok(spec(1, 8, 1, false)));
+        assert_eq!(DiceSpec::parse("2d6-2"), Ok(spec(2, 6, -2, false)));
+        assert_eq!(DiceSpec::parse("d66+10"), Ok(spec(2, 6, 10, true)));
+        for invalid in ["goblin", "2d0", "d", "2d6+", "-1d6", "1d6+x"] {
+            assert_eq!(
+                DiceSpec::parse(invalid),
+                Err(DiceError::NotDice(invalid.to_string())),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_loaded_dice_parse() {
+        let spec = DiceSpec::parse("w[1:3,6:0]d6+1").unwrap();
+        assert_eq!((spec.count, spec.sides, spec.modifier), (1, 6, 1));
+        assert_eq!(spec.weights, [3, 1, 1, 1, 1, 0]);
+        assert_eq!(DiceSpec::parse("w[2:2]d66").unwrap().weights, [1, 2, 1, 1, 1, 1]);
+        assert_eq!(DiceSpec::parse("w[1:3]x").unwrap_err(), DiceError::NotDice("w[1:3]x".into()));
+
+        let bad = |dice: &str| match DiceSpec::parse(dice) {
+            Err(DiceError::BadWeights { reason, .. }) => reason,
+            result => panic!("{}: {:?}", dice, result),
+        };
+        assert_eq!(bad("w[1:3d6"), "no closing ']'");
+        assert_eq!(bad("w[1=3]d6"), "'1=3' isn't <face>:<weight>");
+        assert_eq!(bad("w[1:x]d6"), "'1:x' isn't <face>:<weight>");
+        assert_eq!(bad("w[]d6"), "'' isn't <face>:<weight>");
+        assert_eq!(bad("w[7:2]d6"), "there's no face 7 on a d6");
+        assert_eq!(bad("w[1:2,1:3]d6"), "face 1 is weighted twice");
+        assert_eq!(bad("w[1:0]d1"), "every face weighs 0");
+        assert_eq!(bad("w[1:2]w[1:2]d6"), "weights are given twice");
+        assert_eq!(
+            DiceSpec::parse("w[0:1]d4").unwrap_err().to_string(),
+            "'w[0:1]d4' has bad face weights: there's no face 0 on a d4"
+        );
     }
+
+    #[test]
+    fn test_loaded_dice_roll() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let spec = DiceSpec::parse("w[1:3,6:0]d6").unwrap();
+        let rolls = DiceSpec { count: 8_000, ..spec }.roll(&mut rng).unwrap();
+        let share = |face| rolls.iter().filter(|roll| **roll == face).count() as f64 / 8_000.0;
+        // Faces weigh 3, 1, 1, 1, 1 and 0 out of 7.
+        assert!((share(1) - 3.0 / 7.0).abs() < 0.03, "{}", share(1));
+        assert!((share(2) - 1.0 / 7.0).abs() < 0.03, "{}", share(2));
+        assert_eq!(share(6), 0.0);
+
+        let outcomes = outcome_probabilities("w[1:3,6:0]d6").unwrap();
+        assert_eq!(outcomes.len(), 6);
+        assert!((outcomes[0].1 - 3.0 / 7.0).abs() < 1e-9);
+        assert_eq!(outcomes[5], (6, 0.0));
+        let outcomes = outcome_probabilities("w[1:0]d66").unwrap();
+        assert!(outcomes.iter().all(|(n, p)| (*p == 0.0) == n.to_string().contains('1')));
+        assert_eq!(normalize_dice("W[1:3]D6"), "w[1:3]d6");
+    }
+
+    #[test]
+    fn test_dice_spec_roll() {
+        let mut rng = rand::rng();
+        let spec = DiceSpec::parse("3d1+2").unwrap();
+        let rolls = spec.roll(&mut rng).unwrap();
+        assert_eq!(rolls, [1, 1, 1]);
+        assert_eq!(spec.total(&rolls), 5);
+        assert_eq!(DiceSpec::parse("d66").unwrap().total(&[3, 5]), 35);
+        let too_many = DiceSpec::parse("10001d6").unwrap();
+        assert_eq!(too_many.roll(&mut rng), Err(DiceError::TooManyDice(10_001)));
+    }
+
+    #[test]
+    fn test_validate_table() {
+        let entry = |name: &str, numbers: Vec<u32>| table::Entry {
+            name: name.to_string(),
+            numbers,
+            ..Default::default()
+        };
+        let mut table = table::Table {
+            name: "Monsters".to_string(),
+            dice: "1d8".to_string(),
+            rows: vec![
+                entry("Goblin", vec![1, 2]),
+                table::Entry { min: Some(2), max: Some(3), ..entry("Orc", vec![]) },
+                entry("Dragon", vec![9]),
+                table::Entry { see: Some("Ogre".to_string()), ..entry("Troll", vec![8]) },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_table(&table),
+            [
+                "entry 'Dragon' can never be rolled on 1d8",
+                "entries 'Goblin' and 'Orc' both cover 2",
+                "entry 'Troll' sees 'Ogre', which isn't in the table",
+                "no entry covers 4-7",
+            ]
+        );
+
+        table.fallback = Some("Nothing".to_string());
+        assert_eq!(validate_table(&table).len(), 3);
+        table.dice = "goblins".to_string();
+        assert_eq!(validate_table(&table), ["'goblins' isn't dice a table can roll"]);
+        assert_eq!(format_runs([1, 3, 4, 5, 7]), "1, 3-5, 7");
+        assert_eq!(format_runs([]), "");
+    }
+
+    #[test]
+    fn test_overlapping_numbers() {
+        let entry = |name: &str, numbers: Vec<u32>| table::Entry {
+            name: name.to_string(),
+            numbers,
+            ..Default::default()
+        };
+        let table = table::Table {
+            name: "Weather".to_string(),
+            dice: "1d6".to_string(),
+            rows: vec![
+                entry("Clear", vec![1, 2, 3]),
+                entry("Rain", vec![3, 4]),
+                entry("Storm", vec![5, 6]),
+                table::Entry { min: Some(4), max: Some(6), ..entry("Fog", vec![]) },
+            ],
+            ..Default::default()
+        };
+        let overlaps: Vec<_> = overlapping_entries(&table)
+            .into_iter()
+            .map(|(first, second, total)| (first.name.as_str(), second.name.as_str(), total))
+            .collect();
+        assert_eq!(overlaps, [("Clear", "Rain", 3), ("Rain", "Fog", 4), ("Storm", "Fog", 5)]);
+    }
+
+    #[test]
+    fn test_inverted_range() {
+        let table = table::Table {
+            name: "Loot".to_string(),
+            dice: "1d6".to_string(),
+            rows: vec![
+                table::Entry { name: "Coins".to_string(), max: Some(6), ..Default::default() },
+                table::Entry {
+                    name: "Gem".to_string(),
+                    min: Some(5),
+                    max: Some(2),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(validate_table(&table), ["entry 'Gem' has min 5 above its max 2"]);
+        assert!(overlapping_entries(&table).is_empty());
+    }
+
+    #[test]
+    fn test_entry_ranges() {
+        let table = table::Table {
+            name: "Reaction".to_string(),
+            dice: "2d6".to_string(),
+            rows: vec![
+                table::Entry { name: "Hostile".to_string(), max: Some(5), ..Default::default() },
+                table::Entry {
+                    name: "Wary".to_string(),
+                    numbers: vec![5, 6],
+                    ..Default::default()
+                },
+                table::Entry { name: "Friendly".to_string(), min: Some(9), ..Default::default() },
+                table::Entry { name: "Allied".to_string(), min: Some(12), ..Default::default() },
+                table::Entry { name: "Never".to_string(), min: Some(13), ..Default::default() },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(find_entry(&table, 2).map(|e| e.name.as_str()), Some("Hostile"));
+        assert_eq!(find_entry(&table, 6).map(|e| e.name.as_str()), Some("Wary"));
+        assert!(find_entry(&table, 7).is_none());
+        assert_eq!(find_entry(&table, 12).map(|e| e.name.as_str()), Some("Friendly"));
+
+        let overlaps: Vec<(&str, &str, u32)> = overlapping_entries(&table)
+            .into_iter()
+            .map(|(a, b, total)| (a.name.as_str(), b.name.as_str(), total))
+            .collect();
+        assert_eq!(
+            overlaps,
+            [
+                ("Hostile", "Wary", 5),
+                ("Friendly", "Allied", 12),
+                ("Friendly", "Never", 13),
+                ("Allied", "Never", 13),
+            ]
+        );
+        let unreachable: Vec<&str> =
+            unreachable_entries(&table).iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(unreachable, ["Never"]);
+    }
+
+    #[test]
+    fn test_priority_mode() {
+        let mut table = table::Table {
+            name: "Loot".to_string(),
+            dice: "2d6".to_string(),
+            rows: vec![
+                table::Entry { name: "Common".to_string(), min: Some(2), ..Default::default() },
+                table::Entry {
+                    name: "Rare".to_string(),
+                    min: Some(10),
+                    max: Some(12),
+                    ..Default::default()
+                },
+                table::Entry {
+                    name: "Special".to_string(),
+                    numbers: vec![12],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let name = |table: &table::Table, total| find_entry(table, total).map(|e| e.name.clone());
+        assert_eq!(name(&table, 12).as_deref(), Some("Common"));
+        assert_eq!(validate_table(&table).len(), 3);
+
+        // Smallest range wins.
+        table.priority = true;
+        assert_eq!(name(&table, 9).as_deref(), Some("Common"));
+        assert_eq!(name(&table, 11).as_deref(), Some("Rare"));
+        assert_eq!(name(&table, 12).as_deref(), Some("Special"));
+        assert!(overlapping_entries(&table).is_empty());
+        assert!(validate_table(&table).is_empty());
+        let probabilities = entry_probabilities(&table).unwrap();
+        assert!((probabilities[1] - 5.0 / 36.0).abs() < 1e-9);
+
+        // An explicit priority beats specificity, and ties go to the row listed first.
+        table.rows[0].priority = Some(1);
+        table.rows[0].max = Some(11);
+        assert_eq!(name(&table, 11).as_deref(), Some("Common"));
+        assert_eq!(name(&table, 12).as_deref(), Some("Special"));
+        table.rows[1].priority = Some(2);
+        table.rows[2].priority = Some(2);
+        assert_eq!(name(&table, 11).as_deref(), Some("Rare"));
+        assert_eq!(name(&table, 12).as_deref(), Some("Special"));
+        table.rows[1].min = None;
+        table.rows[1].max = None;
+        table.rows[1].numbers = vec![12];
+        assert_eq!(name(&table, 12).as_deref(), Some("Rare"));
+    }
+
+    #[test]
+    fn test_normalize_dice() {
+        assert_eq!(normalize_dice("d20"), "1d20");
+        assert_eq!(normalize_dice("1D20"), "1d20");
+        assert_eq!(normalize_dice("D66"), "d66");
+        assert_eq!(normalize_dice("2d6+0"), "2d6");
+        assert_eq!(normalize_dice("d8-1"), "1d8-1");
+        assert_eq!(normalize_dice("Weighted"), "weighted");
+    }
+
+    #[test]
+    fn test_assign_weight_ranges() {
+        let weighted = |name: &str, weight| table::Entry {
+            name: name.to_string(),
+            weight: Some(weight),
+            ..Default::default()
+        };
+        let mut table = table::Table {
+            name: "Encounters".to_string(),
+            dice: "d100".to_string(),
+            rows: vec![weighted("Goblins", 50), weighted("Orcs", 30), weighted("Dragon", 20)],
+            ..Default::default()
+        };
+        assert_eq!(assign_weight_ranges(&mut table), None);
+        let ranges: Vec<_> = table.rows.iter().map(|entry| entry.range()).collect();
+        assert_eq!(ranges, [Some(1..=50), Some(51..=80), Some(81..=100)]);
+        assert_eq!(find_entry(&table, 80).map(|e| e.name.as_str()), Some("Orcs"));
+
+        let mut table = table::Table {
+            dice: "2d6".to_string(),
+            rows: vec![weighted("Goblins", 5), weighted("Orcs", 3)],
+            ..table
+        };
+        assert_eq!(
+            assign_weight_ranges(&mut table).as_deref(),
+            Some("weights add up to 8 but 2d6 has 11 results")
+        );
+        assert_eq!(table.rows[1].range(), Some(7..=9));
+    }
+
+    #[test]
+    fn test_entry_probabilities() {
+        let table = table::Table {
+            name: "Reaction".to_string(),
+            dice: "1d4".to_string(),
+            rows: vec![
+                table::Entry { name: "Hostile".to_string(), max: Some(1), ..Default::default() },
+                table::Entry { name: "Wary".to_string(), min: Some(1), ..Default::default() },
+            ],
+            ..Default::default()
+        };
+        assert_eq!(entry_probabilities(&table), Some(vec![0.25, 0.75]));
+
+        let table = table::Table { dice: "weighted".to_string(), ..table };
+        assert_eq!(entry_probabilities(&table), Some(vec![0.5, 0.5]));
+        let table = table::Table { dice: "goblins".to_string(), ..table };
+        assert_eq!(entry_probabilities(&table), None);
+    }
+
+    #[test]
+    fn test_digit_dice() {
+        for _ in 0..100 {
+            let d66 = roll("d66").unwrap();
+            assert!((11..=66).contains(&d66));
+            assert!(d66.to_string().chars().all(|c| ('1'..='6').contains(&c)), "{}", d66);
+            let d666 = roll("d666").unwrap();
+            assert!((111..=666).contains(&d666));
+            assert!(d666.to_string().chars().all(|c| ('1'..='6').contains(&c)), "{}", d666);
+        }
+        assert_eq!(dice_range("d66"), Some(11..=66));
+        assert_eq!(digit_dice("2d66"), None);
+
+        let outcomes = outcome_probabilities("d66").unwrap();
+        assert_eq!(outcomes.len(), 36);
+        assert_eq!(outcomes[6].0, 21);
+    }
+
+    #[test]
+    fn test_roll_with_seed() {
+        use rand::SeedableRng;
+
+        let seeded = |seed| roll_with(&mut rand::rngs::StdRng::seed_from_u64(seed), "3d6");
+        let total = seeded(42).unwrap();
+        assert!((3..=18).contains(&total));
+        for _ in 0..10 {
+            assert_eq!(seeded(42), Some(total));
+        }
+    }
+
+    #[test]
+    fn test_roll_modified() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        assert_eq!(roll_modified_with(&mut rng, "2d1+3"), Some(5));
+        assert_eq!(roll_modified_with(&mut rng, "1d1-4"), Some(-3));
+        assert_eq!(roll_modified_with(&mut rng, "1d1"), Some(1));
+        assert_eq!(roll_modified_with(&mut rng, "1d1+x"), None);
+        assert_eq!(roll_modified_with(&mut rng, "goblin"), None);
+
+        let seeded = || {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(9);
+            (roll_modified_with(&mut rng, "2d6"), roll_modified_with(&mut rng, "1d8+1"))
+        };
+        assert_eq!(seeded(), seeded());
+    }
+
+    #[test]
+    fn test_roll_stats() {
+        use rand::SeedableRng;
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let (mean, min, max) = roll_stats(&mut rng, "1d6", 100_000).unwrap();
+        assert!((mean - 3.5).abs() < 0.05, "{}", mean);
+        assert_eq!((min, max), (1, 6));
+        assert_eq!(roll_stats(&mut rng, "1d6", 0), None);
+        assert_eq!(roll_stats(&mut rng, "monsters", 10), None);
+        assert_eq!(roll_stats(&mut rng, "10001d6", 1_000), None);
+    }
+
+    #[test]
+    fn test_describe_dice() {
+        assert_eq!(
+            describe_dice("2d6").unwrap(),
+            "Rolling 2d6: results cluster around 7 (about 17% each); extremes (2 and 12) are rare \
+             (about 3% each)."
+        );
+        assert!(describe_dice("3d6").unwrap().contains("around 10 and 11"));
+        assert_eq!(
+            describe_dice("1d20").unwrap(),
+            "Rolling 1d20: every result from 1 to 20 is equally likely (about 5% each)."
+        );
+        assert_eq!(describe_dice("weighted"), None);
+    }
+
+    #[test]
+    fn test_tally() {
+        use rand::SeedableRng;
+
+        let table = table::Table {
+            name: "Monsters".to_string(),
+            dice: "1d6".to_string(),
+            rows: vec![table::Entry {
+                name: "Goblin".to_string(),
+                numbers: vec![1, 2, 3],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let counts = tally(&mut rng, &table, 500);
+        assert_eq!(counts.values().sum::<u32>(), 500);
+        assert_eq!(counts.len(), 2);
+        assert!(counts[&None] > 0);
+        assert!(counts[&Some("Goblin".to_string())] > 0);
+    }
+
+    #[test]
+    fn test_interpolate() {
+        assert_eq!(interpolate("{1d1} goblins"), ("1 goblins".to_string(), vec![]));
+        assert_eq!(interpolate("{ 2d1 }x{d1}"), ("2x1".to_string(), vec![]));
+        assert_eq!(interpolate("no braces").0, "no braces");
+
+        let (text, warnings) = interpolate("a {bugbear} and {1d1");
+        assert_eq!(text, "a {bugbear} and {1d1");
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn test_roll_dice_cap() {
+        assert!(roll(&format!("{}d6", MAX_DICE + 1)).is_none());
+        assert!(is_dice_notation(&format!("{}d6", MAX_DICE + 1)));
+
+        let huge = roll(&format!("{}d{}", MAX_DICE, u32::MAX));
+        assert!(huge.is_some_and(|total| total >= MAX_DICE));
+    }
+
+    #[test]
+    fn test_is_dice_notation() {
+        assert!(is_dice_notation("1d20"));
+        assert!(is_dice_notation("d6"));
+        assert!(!is_dice_notation("monsters"));
+        assert!(!is_dice_notation("dragons"));
+        assert!(!is_dice_notation("2d"));
+        assert!(!is_dice_notation("1d0"));
+    }
+
+    #[test]
+    fn test_outcome_probabilities() {
+        let outcomes = outcome_probabilities("2d6").unwrap();
+        assert_eq!(outcomes.len(), 11);
+        assert_eq!(outcomes[0].0, 2);
+        assert!((outcomes[5].1 - 6.0 / 36.0).abs() < 1e-9);
+        assert!((outcomes.iter().map(|(_, p)| p).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(outcome_probabilities("1000d1000").is_none());
+    }
+
+    #[test]
+    fn test_expected_severity_uniform() {
+        let table = table::Table {
+            name: "Uniform".to_string(),
+            dice: "1d6".to_string(),
+            rows: (1..=6)
+                .map(|n| table::Entry {
+                    name: format!("Severity {}", n),
+                    numbers: vec![n],
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let (severity, coverage) = expected_severity(&table, "1d6").unwrap();
+        assert!((severity - 3.5).abs() < 1e-9);
+        assert!((coverage - 1.0).abs() < 1e-9);
+
+        let (_, coverage) = expected_severity(&table, "1d12").unwrap();
+        assert!((coverage - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_unreachable_entries() {
+        let table = table::Table {
+            name: "Small".to_string(),
+            dice: "1d4".to_string(),
+            rows: vec![
+                table::Entry {
+                    name: "Reachable".to_string(),
+                    numbers: vec![4, 5],
+                    ..Default::default()
+                },
+                table::Entry {
+                    name: "Unreachable".to_string(),
+                    numbers: vec![12],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let unreachable: Vec<&str> = unreachable_entries(&table)
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(unreachable, ["Unreachable"]);
+        assert_eq!(dice_range("2d6"), Some(2..=12));
+    }
+
+    #[test]
+    fn test_weighted_boundaries() {
+        let toml_str = r#"
+            [[table]]
+            name = "Weighted"
+            dice = "weighted"
+            [[table.rows]]
+            name = "Rare"
+            weight = 1
+            [[table.rows]]
+            name = "Uncommon"
+            weight = 2
+            [[table.rows]]
+            name = "Common"
+            weight = 3
+
+            [[table]]
+            name = "Numbered"
+            dice = "1d6"
+            [[table.rows]]
+            name = "Anything"
+            numbers = [1, 2, 3, 4, 5, 6]
+        "#;
+        let tables = parse_tables(toml_str).unwrap();
+        let table = &tables[0];
+
+        let cumulative = cumulative_weights(table);
+        assert_eq!(cumulative, [1, 3, 6]);
+        let picks: Vec<Option<usize>> =
+            (0..=7).map(|roll| weighted_index(&cumulative, roll)).collect();
+        assert_eq!(picks, [None, Some(0), Some(1), Some(1), Some(2), Some(2), Some(2), None]);
+
+        let probabilities = weighted_probabilities(table);
+        assert!((probabilities[0] - 1.0 / 6.0).abs() < 1e-9);
+        assert!((probabilities[2] - 0.5).abs() < 1e-9);
+
+        let (_, rolls, entry) = roll_on(table, &table.dice);
+        assert!(rolls.is_empty() && entry.is_some());
+        let (roll, _, entry) = roll_on(&tables[1], &tables[1].dice);
+        assert!((1..=6).contains(&roll) && entry.is_some());
+    }
+
+    #[test]
+    fn test_time_of_day() {
+        assert_eq!(parse_time_of_day("06:00"), Some(360));
+        assert_eq!(parse_time_of_day("0:05"), Some(5));
+        assert_eq!(parse_time_of_day("24:00"), None);
+        assert_eq!(parse_time_of_day("noon"), None);
+
+        assert_eq!(minutes_until(8 * 60, 6 * 60), 22 * 60);
+        assert_eq!(minutes_until(5 * 60, 6 * 60), 60);
+        assert_eq!(minutes_until(6 * 60, 6 * 60), MINUTES_PER_DAY);
+        assert_eq!(minutes_until(MINUTES_PER_DAY + 23 * 60, 0), 60);
+    }
+
+    #[test]
+    fn test_time_band_labels() {
+        let label = |time: &str| time_band(DEFAULT_TIME_BANDS, parse_time_of_day(time).unwrap());
+        assert_eq!(label("05:00"), Some("dawn"));
+        assert_eq!(label("07:59"), Some("dawn"));
+        assert_eq!(label("09:15"), Some("morning"));
+        assert_eq!(label("12:00"), Some("afternoon"));
+        assert_eq!(label("18:30"), Some("evening"));
+        assert_eq!(label("23:00"), Some("night"));
+        assert_eq!(label("02:00"), Some("night"));
+        assert_eq!(time_band(DEFAULT_TIME_BANDS, MINUTES_PER_DAY + 9 * 60), Some("morning"));
+        assert_eq!(time_band([], 0), None);
+    }
+
+    #[test]
+    fn test_parse_check() {
+        assert_eq!(parse_check("1d6<=2"), Some(("1d6", Comparison::LessOrEqual, 2)));
+        assert_eq!(parse_check("1d20>=15"), Some(("1d20", Comparison::GreaterOrEqual, 15)));
+        assert_eq!(parse_check("2d6<7"), Some(("2d6", Comparison::Less, 7)));
+        assert_eq!(parse_check("d8>4"), Some(("d8", Comparison::Greater, 4)));
+        assert_eq!(parse_check("1d6=1"), Some(("1d6", Comparison::Equal, 1)));
+        assert_eq!(parse_check("1d6"), None);
+        assert_eq!(parse_check("monsters<=2"), None);
+        assert_eq!(parse_check("1d6<=two"), None);
+    }
+
+    #[test]
+    fn test_comparison_at_threshold() {
+        assert!(Comparison::LessOrEqual.holds(2, 2));
+        assert!(Comparison::GreaterOrEqual.holds(2, 2));
+        assert!(Comparison::Equal.holds(2, 2));
+        assert!(!Comparison::Less.holds(2, 2));
+        assert!(!Comparison::Greater.holds(2, 2));
+    }
+
+    #[test]
+    fn test_parse_tables_json() {
+        let json = r#"{"table": [
+            {"name": "Loot", "dice": "1d6", "rows": [{"name": "Gold", "numbers": [1]}]}
+        ]}"#;
+        let tables = parse_tables_json(json).unwrap();
+        assert_eq!(tables[0].name, "Loot");
+        assert_eq!(tables[0].rows[0].numbers, [1]);
+        assert!(parse_tables_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_read_includes() {
+        let dir = std::env::temp_dir().join(format!("wayline-includes-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("more")).unwrap();
+        let base = r#"
+            include = ["more/monsters.toml", "missing.toml"]
+            [[table]]
+            name = "Base"
+            dice = "1d6"
+            rows = []
+        "#;
+        // Includes resolve relative to the including file, so `../base.toml` is the base file.
+        let monsters = r#"
+            include = ["../base.toml"]
+            [[table]]
+            name = "Monsters"
+            dice = "1d6"
+            rows = []
+        "#;
+        std::fs::write(dir.join("base.toml"), base).unwrap();
+        std::fs::write(dir.join("more/monsters.toml"), monsters).unwrap();
+
+        let (included, errors) = read_includes(&dir.join("base.toml"), base);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(included.len(), 1);
+        assert_eq!(included[0].0, dir.join("more/monsters.toml"));
+        assert_eq!(parse_tables(&included[0].1).unwrap()[0].name, "Monsters");
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("cycle"));
+        assert!(errors[1].contains("missing.toml"));
+    }
+
+    #[test]
+    fn test_parse_tables_lenient() {
+        let toml_str = r#"
+            [[table]]
+            name = "Good"
+            dice = "1d6"
+            [[table.rows]]
+            name = "Result A"
+            numbers = [1, 2, 3, 4, 5, 6]
+
+            [[table]]
+            name = "Broken"
+            [[table.rows]]
+            name = "Result B"
+            numbers = "oops"
+        "#;
+
+        assert!(parse_tables(toml_str).is_err());
+
+        let (tables, errors) = parse_tables_lenient(toml_str).unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "Good");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 1);
+    }
+
+    #[test]
+    fn test_parse_table_metadata() {
+        let toml_str = r#"
+            [[table]]
+            name = "Attributed"
+            dice = "1d6"
+            author = "A. Referee"
+            source = "Zine #3"
+            edition = "2nd"
+            rows = []
+
+            [[table]]
+            name = "Plain"
+            dice = "1d6"
+            rows = []
+        "#;
+
+        let tables = parse_tables(toml_str).unwrap();
+        assert_eq!(tables[0].author.as_deref(), Some("A. Referee"));
+        assert_eq!(tables[0].source.as_deref(), Some("Zine #3"));
+        assert_eq!(tables[0].edition.as_deref(), Some("2nd"));
+        assert!(tables[1].author.is_none());
+        assert!(tables[1].source.is_none());
+        assert!(tables[1].edition.is_none());
+    }
+
+    #[test]
+    fn test_parse_table_description() {
+        let toml_str = r#"
+            [[table]]
+            name = "Treasures"
+            dice = "1d6"
+            description = "Loot found in lairs and hoards."
+            rows = []
+
+            [[table]]
+            name = "Plain"
+            dice = "1d6"
+            rows = []
+        "#;
+
+        let tables = parse_tables(toml_str).unwrap();
+        assert_eq!(
+            tables[0].description.as_deref(),
+            Some("Loot found in lairs and hoards.")
+        );
+        assert!(tables[1].description.is_none());
+    }
+
     #[test]
     fn test_roll_on() {
         let table = table::Table {
@@ -58,19 +1640,27 @@ mod tests {
                 table::Entry {
                     name: "Result A".to_string(),
                     numbers: vec![1, 2],
+                    ..Default::default()
                 },
                 table::Entry {
                     name: "Result B".to_string(),
                     numbers: vec![3, 4],
+                    ..Default::default()
                 },
                 table::Entry {
                     name: "Result C".to_string(),
                     numbers: vec![5, 6],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
-        let (roll_value, entry) = roll_on(&table, "1d6");
-        assert!(roll_value >= 1 && roll_value <= 6);
+        let (roll_value, _, entry) = roll_on(&table, "1d6");
+        assert!((1..=6).contains(&roll_value));
         assert!(entry.is_some());
+
+        let (roll_value, rolls, _) = roll_on(&table, "3d6");
+        assert_eq!(rolls.len(), 3);
+        assert_eq!(rolls.iter().sum::<u32>(), roll_value);
     }
 }
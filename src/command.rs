@@ -1,58 +1,452 @@
+use crate::api;
+
+/// Most minutes a single `add` or `sub` moves the clock: one in-game year.
+pub const MAX_MINUTES: u32 = 365 * api::MINUTES_PER_DAY;
+
+/// Most rolls `avg` makes, so a typo can't freeze the UI.
+pub const MAX_AVERAGE_ROLLS: u32 = 1_000_000;
+
+/// Most rolls `sample` makes.
+pub const MAX_SAMPLE_ROLLS: u32 = 100_000;
+
+/// Most times a single `roll <times> <table>` rolls, since each roll is a line of output.
+pub const MAX_REPEAT_ROLLS: u32 = 100;
+
+/// Largest bonus or penalty `whatif` looks at, either way.
+pub const MAX_WHATIF_MODIFIER: i64 = 1_000;
+
+/// Most attempts `rolluntil` makes, and how many it makes when no limit is given.
+pub const MAX_ROLL_UNTIL: u32 = 1_000;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Command {
-    RollTable(Option<String>),
-    RollDice(String),
+    RollTable(Option<String>, Option<String>, Vec<String>), // table name, dice override, modifiers
+    RollJson(Option<String>, Option<String>, Vec<String>, Option<u32>), // as `RollTable`, times
+    RollMany { times: u32, target: Option<String>, unique: bool },
+    Peek(Option<String>), // table name
+    Force { table: String, row: usize }, // 1-based row
+    RollUntil { target_entry: String, limit: u32 },
+    RollDice(String, Option<u32>), // dice, minimum result
+    DiceHelp,
+    SeededRoll { seed: u64, dice: String },
+    Average { times: u32, dice: String },
+    Oppose { left: String, right: String },
+    Sample { times: u32, target: Option<String> },
+    Gen(Option<String>), // table name
+    Total(String), // dice to roll and add to the running total
+    TotalReset,
     List(Option<String>),
+    More,
+    Tables,
+    FindTable(Option<String>), // dice
+    Last,
+    RecentRolls,
     Time,
     Add(u32), // in minutes
+    AddRolled(String), // dice for how many minutes to add
+    Sub(u32), // in minutes
+    Travel { minutes: u32, check: Option<String> }, // encounter check before rolling, if any
+    ClockNew(String),
+    ClockUse(String),
+    Clocks,
+    Turn,
+    Watch,
+    Goto(String), // time-of-day anchor
+    SetDay { day: i64, relative: bool }, // `day 5`, or `day +2` for two days on
     Use(String),
-    Help,
+    Back,
+    Undo,
+    Redo,
+    Lookup(String),
+    Ev(Option<String>), // table name
+    Describe(Option<String>), // table name or dice
+    Compare(String, String), // table names
+    Odds(Option<String>), // table name
+    WhatIf { target: Option<String>, modifier: i64 },
+    Matrix(String, String), // table names
+    Check(String, bool), // check expression, whether to roll the current table on success
+    Help(Option<String>),
+    Version,
+    Clear,
+    Reset,
+    Summary,
+    ToggleRollLog,
+    ScrollTop,
+    ScrollBottom,
+    ToggleAutoscroll,
+    Reload,
+    Edit,
+    Prompt(String),
     Unknown(String),
 }
 
+/// Splits `input` into whitespace-separated tokens, keeping whitespace inside double quotes.
+/// An unterminated quote runs to the end of the input.
+pub fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    // Tracked separately from `current` so that `""` still yields an (empty) token.
+    let mut in_token = false;
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_token = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
 pub fn parse_command(input: &str) -> Command {
-    let parts: Vec<&str> = input.split_whitespace().collect();
+    let parts = tokenize(input);
     if parts.is_empty() {
         return Command::Unknown(input.to_string());
     }
 
+    // `+2d6` is shorthand for `total 2d6`.
+    if let Some(dice) = parts[0].strip_prefix('+')
+        && parts.len() == 1
+        && !dice.is_empty()
+    {
+        return Command::Total(dice.to_lowercase());
+    }
+
     match parts[0].to_lowercase().as_str() {
-        "roll" => {
-            if parts.len() == 1 {
-                Command::RollTable(None)
+        command @ ("roll" | "roll-json") => {
+            // `roll monsters --json` (or `roll-json monsters`) prints the result as JSON.
+            let (json_flags, parts): (Vec<String>, Vec<String>) =
+                parts[1..].iter().cloned().partition(|part| part.eq_ignore_ascii_case("--json"));
+            let json = command == "roll-json" || !json_flags.is_empty();
+
+            // `:night` applies the table's "night" modifier to the roll.
+            let (modifiers, parts): (Vec<String>, Vec<String>) =
+                parts.into_iter().partition(|part| part.len() > 1 && part.starts_with(':'));
+            let modifiers: Vec<String> =
+                modifiers.iter().map(|modifier| modifier[1..].to_lowercase()).collect();
+
+            // `roll 3 monsters` rolls the table three times; `unique` rerolls repeated entries.
+            if modifiers.is_empty()
+                && !json
+                && let Some(times) = parts.first().and_then(|part| part.parse::<u32>().ok())
+                && times > 0
+            {
+                let mut rest = &parts[1..];
+                let unique = rest.last().is_some_and(|part| part.eq_ignore_ascii_case("unique"));
+                if unique {
+                    rest = &rest[..rest.len() - 1];
+                }
+                let target = (!rest.is_empty()).then(|| rest.join(" "));
+                return Command::RollMany { times, target, unique };
+            }
+
+            // `roll [table name] with <dice>` rolls the table on different dice.
+            // A trailing "with" that isn't followed by dice is part of the table name.
+            let (name_parts, dice_override) = match &parts[..] {
+                [name @ .., with, dice]
+                    if with.eq_ignore_ascii_case("with")
+                        && api::is_dice_notation(&dice.to_lowercase()) =>
+                {
+                    (name, Some(dice.to_lowercase()))
+                }
+                name => (name, None),
+            };
+            let table_name = (!name_parts.is_empty()).then(|| name_parts.join(" "));
+            if json {
+                // `roll 3 monsters --json` gives an array of three rolls.
+                let (times, table_name) = match name_parts {
+                    [count, rest @ ..] if let Ok(times) = count.parse::<u32>() && times > 0 => {
+                        (Some(times), (!rest.is_empty()).then(|| rest.join(" ")))
+                    }
+                    _ => (None, table_name),
+                };
+                Command::RollJson(table_name, dice_override, modifiers, times)
             } else {
-                let table_name = parts[1..].join(" ").to_lowercase();
-                Command::RollTable(Some(table_name))
+                Command::RollTable(table_name, dice_override, modifiers)
+            }
+        }
+        "peek" => Command::Peek((parts.len() > 1).then(|| parts[1..].join(" "))),
+        "force" => match &parts[1..] {
+            [table @ .., row] if !table.is_empty() && let Ok(row) = row.parse::<usize>() => {
+                Command::Force { table: table.join(" "), row }
+            }
+            _ => Command::Unknown(input.to_string()),
+        },
+        "rolluntil" | "roll-until" => {
+            // `rolluntil dragon sighting 50` gives up after 50 rolls.
+            let (name_parts, limit) = match &parts[1..] {
+                [name @ .., limit] if !name.is_empty() && limit.parse::<u32>().is_ok() => {
+                    (name, limit.parse().unwrap_or(MAX_ROLL_UNTIL))
+                }
+                name => (name, MAX_ROLL_UNTIL),
+            };
+            if name_parts.is_empty() {
+                return Command::Unknown(input.to_string());
             }
+            Command::RollUntil {
+                target_entry: name_parts.join(" ").to_lowercase(),
+                limit: limit.min(MAX_ROLL_UNTIL),
+            }
+        }
+        "avg" => {
+            if parts.len() == 3
+                && let Ok(times) = parts[1].parse::<u32>()
+                && times > 0
+            {
+                let times = times.min(MAX_AVERAGE_ROLLS);
+                return Command::Average { times, dice: parts[2].to_lowercase() };
+            }
+            Command::Unknown(input.to_string())
+        }
+        "sample" => {
+            if parts.len() >= 2
+                && let Ok(times) = parts[1].parse::<u32>()
+                && times > 0
+            {
+                let target = (parts.len() > 2).then(|| parts[2..].join(" "));
+                return Command::Sample { times: times.min(MAX_SAMPLE_ROLLS), target };
+            }
+            Command::Unknown(input.to_string())
+        }
+        "oppose" => {
+            // `oppose 2d6 vs 1d8+1`; each side may be written with spaces, as in `1d8 + 1`.
+            match parts[1..].iter().position(|part| part.eq_ignore_ascii_case("vs")) {
+                Some(at) if at > 0 && at + 2 < parts.len() => Command::Oppose {
+                    left: parts[1..=at].concat().to_lowercase(),
+                    right: parts[at + 2..].concat().to_lowercase(),
+                },
+                _ => Command::Unknown(input.to_string()),
+            }
+        }
+        "sroll" => {
+            if parts.len() == 3
+                && let Ok(seed) = parts[1].parse::<u64>()
+            {
+                return Command::SeededRoll { seed, dice: parts[2].to_lowercase() };
+            }
+            Command::Unknown(input.to_string())
+        }
+        "gen" => if parts.len() == 1 {
+            Command::Gen(None)
+        } else {
+            Command::Gen(Some(parts[1..].join(" ")))
         }
         "list" => if parts.len() == 1 {
             Command::List(None)
         } else {
-            Command::List(Some(parts[1..].join(" ").to_lowercase()))
+            Command::List(Some(parts[1..].join(" ")))
+        }
+        "more" => Command::More,
+        "tables" => Command::Tables,
+        "rolls" => Command::RecentRolls,
+        "history" if parts.len() == 2 && parts[1].eq_ignore_ascii_case("rolls") => {
+            Command::RecentRolls
+        }
+        "compare" => {
+            // `compare monsters beasts`, or `compare wilderness encounters vs city events`
+            // for names with spaces.
+            match parts[1..].iter().position(|part| part.eq_ignore_ascii_case("vs")) {
+                Some(at) if at > 0 && at + 2 < parts.len() => {
+                    Command::Compare(parts[1..=at].join(" "), parts[at + 2..].join(" "))
+                }
+                None if parts.len() == 3 => Command::Compare(parts[1].clone(), parts[2].clone()),
+                _ => Command::Unknown(input.to_string()),
+            }
         }
+        "odds" | "stats-table" => Command::Odds((parts.len() > 1).then(|| parts[1..].join(" "))),
+        "whatif" | "what-if" => {
+            // `whatif +2` for the current table, `whatif wilderness encounters -1` for another.
+            match &parts[1..] {
+                [name @ .., modifier]
+                    if modifier.starts_with(['+', '-'])
+                        && let Ok(modifier) = modifier.parse::<i64>() =>
+                {
+                    let target = (!name.is_empty()).then(|| name.join(" "));
+                    let modifier = modifier.clamp(-MAX_WHATIF_MODIFIER, MAX_WHATIF_MODIFIER);
+                    Command::WhatIf { target, modifier }
+                }
+                _ => Command::Unknown(input.to_string()),
+            }
+        }
+        "matrix" | "roll-matrix" => {
+            // `matrix terrain creatures`, or `matrix hex terrain + wandering monsters`.
+            match parts[1..].iter().position(|part| part == "+") {
+                Some(at) if at > 0 && at + 2 < parts.len() => {
+                    Command::Matrix(parts[1..=at].join(" "), parts[at + 2..].join(" "))
+                }
+                None if parts.len() == 3 => Command::Matrix(parts[1].clone(), parts[2].clone()),
+                _ => Command::Unknown(input.to_string()),
+            }
+        }
+        "findtable" | "find-table" => match &parts[1..] {
+            [] => Command::FindTable(None),
+            dice => Command::FindTable(Some(dice.concat().to_lowercase())),
+        },
+        "last" => Command::Last,
+        "reload" => Command::Reload,
+        "edit" => Command::Edit,
+        "clear" => Command::Clear,
+        "reset" => Command::Reset,
+        "summary" => Command::Summary,
+        "log" => Command::ToggleRollLog,
+        "top" => Command::ScrollTop,
+        "bottom" => Command::ScrollBottom,
+        "autoscroll" => Command::ToggleAutoscroll,
+        "version" | "about" => Command::Version,
         "time" => Command::Time,
+        "turn" => Command::Turn,
+        "watch" => Command::Watch,
+        "goto" => {
+            if parts.len() == 2 {
+                return Command::Goto(parts[1].to_lowercase());
+            }
+            Command::Unknown(input.to_string())
+        }
+        "day" | "goto-day" => match &parts[1..] {
+            [day] if let Ok(number) = day.parse::<i64>() => {
+                Command::SetDay { day: number, relative: day.starts_with(['+', '-']) }
+            }
+            _ => Command::Unknown(input.to_string()),
+        },
+        "back" | "prev" => Command::Back,
+        "undo" => Command::Undo,
+        "redo" => Command::Redo,
         "use" => {
             let table_name = if parts.len() >= 2 {
-                parts[1..].join(" ").to_lowercase()
+                parts[1..].join(" ")
             } else {
                 "".into()
             };
             Command::Use(table_name)
         }
         "dice" => {
+            match &parts[1..] {
+                [help] if help.eq_ignore_ascii_case("help") => Command::DiceHelp,
+                [dice] => Command::RollDice(dice.to_string(), None),
+                [dice, min, floor] if min.eq_ignore_ascii_case("min") => match floor.parse() {
+                    Ok(floor) => Command::RollDice(dice.to_string(), Some(floor)),
+                    Err(_) => Command::Unknown(input.to_string()),
+                },
+                _ => Command::Unknown(input.to_string()),
+            }
+        }
+        "total" => {
             if parts.len() == 2 {
-                return Command::RollDice(parts[1].to_string());
+                if parts[1].eq_ignore_ascii_case("reset") {
+                    return Command::TotalReset;
+                }
+                return Command::Total(parts[1].to_lowercase());
             }
             Command::Unknown(input.to_string())
         }
         "add" => {
             if parts.len() == 2
                 && let Ok(minutes) = parts[1].parse::<u32>() {
-                    return Command::Add(minutes);
+                    return Command::Add(minutes.min(MAX_MINUTES));
+                }
+            // `add 1d10` rolls for how long something took.
+            if parts.len() == 2 {
+                let dice = parts[1].to_lowercase();
+                if api::DiceSpec::parse(&dice).is_ok() {
+                    return Command::AddRolled(dice);
+                }
+            }
+            Command::Unknown(input.to_string())
+        }
+        "sub" => {
+            if parts.len() == 2
+                && let Ok(minutes) = parts[1].parse::<u32>() {
+                    return Command::Sub(minutes.min(MAX_MINUTES));
                 }
             Command::Unknown(input.to_string())
         }
-        "help" => Command::Help,
+        "travel" => {
+            // `travel 60 1d6<=1` only rolls for an encounter if the check succeeds.
+            if parts.len() >= 2
+                && let Ok(minutes) = parts[1].parse::<u32>()
+            {
+                let check = (parts.len() > 2).then(|| parts[2..].concat().to_lowercase());
+                return Command::Travel { minutes: minutes.min(MAX_MINUTES), check };
+            }
+            Command::Unknown(input.to_string())
+        }
+        "clock" => {
+            if parts.len() == 1 {
+                return Command::Clocks;
+            }
+            let name = parts[2..].join(" ");
+            match parts[1].to_lowercase().as_str() {
+                "new" if !name.is_empty() => Command::ClockNew(name),
+                "use" if !name.is_empty() => Command::ClockUse(name),
+                _ => Command::Unknown(input.to_string()),
+            }
+        }
+        "lookup" => {
+            if parts.len() >= 2 {
+                return Command::Lookup(parts[1..].join(" ").to_lowercase());
+            }
+            Command::Unknown(input.to_string())
+        }
+        "ev" => if parts.len() == 1 {
+            Command::Ev(None)
+        } else {
+            Command::Ev(Some(parts[1..].join(" ")))
+        }
+        "describe" => if parts.len() == 1 {
+            Command::Describe(None)
+        } else {
+            Command::Describe(Some(parts[1..].join(" ")))
+        }
+        "check" => {
+            // `check 1d6<=2 then roll` rolls the current table if the check succeeds.
+            let (check_parts, then_roll) = match &parts[1..] {
+                [check @ .., then, roll]
+                    if then.eq_ignore_ascii_case("then") && roll.eq_ignore_ascii_case("roll") =>
+                {
+                    (check, true)
+                }
+                check => (check, false),
+            };
+            if check_parts.is_empty() {
+                return Command::Unknown(input.to_string());
+            }
+            Command::Check(check_parts.concat().to_lowercase(), then_roll)
+        }
+        "prompt" => {
+            // Unquoted, the prompt is the rest of the raw input so trailing spaces, as in
+            // "] ", survive without needing quotes.
+            let rest = input
+                .trim_start()
+                .split_once(char::is_whitespace)
+                .map(|(_, rest)| rest)
+                .unwrap_or_default();
+            if rest.trim_start().starts_with('"') {
+                Command::Prompt(parts[1..].join(" "))
+            } else {
+                Command::Prompt(rest.to_string())
+            }
+        }
+        "help" => if parts.len() == 1 {
+            Command::Help(None)
+        } else {
+            Command::Help(Some(parts[1..].join(" ").to_lowercase()))
+        }
         _ => Command::Unknown(input.to_string()),
     }
 }
@@ -62,14 +456,265 @@ mod tests {
     use super::*;
     #[test]
     fn test_parse_command() {
-        assert_eq!(parse_command("roll"), Command::RollTable(None));
-        assert_eq!(parse_command("roll monsters"), Command::RollTable(Some("monsters".to_string())));
+        assert_eq!(parse_command("roll"), Command::RollTable(None, None, vec![]));
+        assert_eq!(
+            parse_command("roll monsters --json :night"),
+            Command::RollJson(Some("monsters".to_string()), None, vec!["night".to_string()], None)
+        );
+        assert_eq!(parse_command("roll-json"), Command::RollJson(None, None, vec![], None));
+        assert_eq!(
+            parse_command("roll 3 monsters --JSON"),
+            Command::RollJson(Some("monsters".to_string()), None, vec![], Some(3))
+        );
+        assert_eq!(parse_command("roll-json 2"), Command::RollJson(None, None, vec![], Some(2)));
+        assert_eq!(parse_command("roll monsters"), Command::RollTable(Some("monsters".to_string()), None, vec![]));
+        assert_eq!(parse_command("roll 1d20"), Command::RollTable(Some("1d20".to_string()), None, vec![]));
         assert_eq!(parse_command("list"), Command::List(None));
+        assert_eq!(parse_command("gen"), Command::Gen(None));
+        assert_eq!(parse_command("gen City Events"), Command::Gen(Some("City Events".to_string())));
+        assert_eq!(
+            parse_command("rolluntil Dragon Sighting"),
+            Command::RollUntil { target_entry: "dragon sighting".to_string(), limit: MAX_ROLL_UNTIL }
+        );
+        assert_eq!(
+            parse_command("rolluntil dragon 50"),
+            Command::RollUntil { target_entry: "dragon".to_string(), limit: 50 }
+        );
+        assert_eq!(
+            parse_command("rolluntil 12"),
+            Command::RollUntil { target_entry: "12".to_string(), limit: MAX_ROLL_UNTIL }
+        );
+        assert_eq!(parse_command("rolluntil"), Command::Unknown("rolluntil".to_string()));
+        assert_eq!(parse_command("more"), Command::More);
+        assert_eq!(parse_command("tables"), Command::Tables);
+        assert_eq!(parse_command("last"), Command::Last);
+        assert_eq!(parse_command("reload"), Command::Reload);
+        assert_eq!(parse_command("edit"), Command::Edit);
+        assert_eq!(parse_command("log"), Command::ToggleRollLog);
+        assert_eq!(parse_command("top"), Command::ScrollTop);
+        assert_eq!(parse_command("Bottom"), Command::ScrollBottom);
+        assert_eq!(parse_command("autoscroll"), Command::ToggleAutoscroll);
+        assert_eq!(parse_command("peek"), Command::Peek(None));
+        assert_eq!(
+            parse_command("force city events 3"),
+            Command::Force { table: "city events".to_string(), row: 3 }
+        );
+        assert_eq!(parse_command("force 3"), Command::Unknown("force 3".to_string()));
+        assert_eq!(parse_command("force monsters"), Command::Unknown("force monsters".to_string()));
+        assert_eq!(parse_command("rolls"), Command::RecentRolls);
+        assert_eq!(parse_command("history Rolls"), Command::RecentRolls);
+        assert_eq!(
+            parse_command("compare monsters beasts"),
+            Command::Compare("monsters".to_string(), "beasts".to_string())
+        );
+        assert_eq!(
+            parse_command("compare city events VS wilderness"),
+            Command::Compare("city events".to_string(), "wilderness".to_string())
+        );
+        assert_eq!(parse_command("compare monsters"), Command::Unknown("compare monsters".to_string()));
+        assert_eq!(parse_command("peek Monsters"), Command::Peek(Some("Monsters".to_string())));
+        assert_eq!(parse_command("findtable"), Command::FindTable(None));
+        assert_eq!(parse_command("odds"), Command::Odds(None));
+        assert_eq!(
+            parse_command("whatif city events +2"),
+            Command::WhatIf { target: Some("city events".to_string()), modifier: 2 }
+        );
+        assert_eq!(parse_command("whatif -1"), Command::WhatIf { target: None, modifier: -1 });
+        assert_eq!(
+            parse_command("whatif +99999"),
+            Command::WhatIf { target: None, modifier: MAX_WHATIF_MODIFIER }
+        );
+        assert_eq!(parse_command("whatif monsters 2"), Command::Unknown("whatif monsters 2".into()));
+        assert_eq!(parse_command("whatif"), Command::Unknown("whatif".to_string()));
+        assert_eq!(
+            parse_command("stats-table city events"),
+            Command::Odds(Some("city events".to_string()))
+        );
+        assert_eq!(
+            parse_command("matrix terrain creatures"),
+            Command::Matrix("terrain".to_string(), "creatures".to_string())
+        );
+        assert_eq!(
+            parse_command("roll-matrix hex terrain + wandering monsters"),
+            Command::Matrix("hex terrain".to_string(), "wandering monsters".to_string())
+        );
+        assert_eq!(parse_command("matrix terrain"), Command::Unknown("matrix terrain".to_string()));
+        assert_eq!(parse_command("matrix a + "), Command::Unknown("matrix a + ".to_string()));
+        assert_eq!(parse_command("find-table 2D6"), Command::FindTable(Some("2d6".to_string())));
+        assert_eq!(parse_command("clear"), Command::Clear);
+        assert_eq!(parse_command("Reset"), Command::Reset);
+        assert_eq!(parse_command("summary"), Command::Summary);
+        assert_eq!(parse_command("version"), Command::Version);
+        assert_eq!(parse_command("About"), Command::Version);
         assert_eq!(parse_command("time"), Command::Time);
         assert_eq!(parse_command("use treasures"), Command::Use("treasures".to_string()));
-        assert_eq!(parse_command("dice 2d6"), Command::RollDice("2d6".to_string()));
+        assert_eq!(parse_command("use Gems"), Command::Use("Gems".to_string()));
+        assert_eq!(parse_command("back"), Command::Back);
+        assert_eq!(parse_command("prev"), Command::Back);
+        assert_eq!(parse_command("undo"), Command::Undo);
+        assert_eq!(parse_command("redo"), Command::Redo);
+        assert_eq!(parse_command("dice 2d6"), Command::RollDice("2d6".to_string(), None));
+        assert_eq!(parse_command("dice help"), Command::DiceHelp);
+        assert_eq!(
+            parse_command("sroll 42 3D6"),
+            Command::SeededRoll { seed: 42, dice: "3d6".to_string() }
+        );
+        assert_eq!(parse_command("sroll 3d6"), Command::Unknown("sroll 3d6".to_string()));
+        assert_eq!(
+            parse_command("avg 1000 2d6"),
+            Command::Average { times: 1000, dice: "2d6".to_string() }
+        );
+        assert_eq!(
+            parse_command("avg 99999999 1d6"),
+            Command::Average { times: MAX_AVERAGE_ROLLS, dice: "1d6".to_string() }
+        );
+        assert_eq!(parse_command("avg 0 1d6"), Command::Unknown("avg 0 1d6".to_string()));
+        assert_eq!(
+            parse_command("oppose 2d6 VS 1d8 + 1"),
+            Command::Oppose { left: "2d6".to_string(), right: "1d8+1".to_string() }
+        );
+        assert_eq!(parse_command("oppose 2d6 vs"), Command::Unknown("oppose 2d6 vs".to_string()));
+        assert_eq!(parse_command("oppose vs 2d6"), Command::Unknown("oppose vs 2d6".to_string()));
+        assert_eq!(parse_command("sample 100"), Command::Sample { times: 100, target: None });
+        assert_eq!(
+            parse_command("sample 100 Wilderness Encounters"),
+            Command::Sample { times: 100, target: Some("Wilderness Encounters".to_string()) }
+        );
+        assert_eq!(parse_command("sample monsters"), Command::Unknown("sample monsters".to_string()));
+        assert_eq!(parse_command("dice 2d6 min 7"), Command::RollDice("2d6".to_string(), Some(7)));
+        assert_eq!(parse_command("dice 2d6 min x"), Command::Unknown("dice 2d6 min x".to_string()));
         assert_eq!(parse_command("add 15"), Command::Add(15));
-        assert_eq!(parse_command("help"), Command::Help);
+        assert_eq!(parse_command("add 1D10"), Command::AddRolled("1d10".to_string()));
+        assert_eq!(parse_command("add 2d6+3"), Command::AddRolled("2d6+3".to_string()));
+        assert_eq!(parse_command("add soon"), Command::Unknown("add soon".to_string()));
+        assert_eq!(parse_command("add 4294967295"), Command::Add(MAX_MINUTES));
+        assert_eq!(parse_command("travel 60"), Command::Travel { minutes: 60, check: None });
+        assert_eq!(
+            parse_command("travel 60 1D6 <= 1"),
+            Command::Travel { minutes: 60, check: Some("1d6<=1".to_string()) }
+        );
+        assert_eq!(parse_command("travel"), Command::Unknown("travel".to_string()));
+        assert_eq!(parse_command("sub 99999999"), Command::Sub(MAX_MINUTES));
+        assert_eq!(parse_command("+2d6"), Command::Total("2d6".to_string()));
+        assert_eq!(parse_command("total 2d6"), Command::Total("2d6".to_string()));
+        assert_eq!(parse_command("total reset"), Command::TotalReset);
+        assert_eq!(parse_command("+"), Command::Unknown("+".to_string()));
+        assert_eq!(parse_command("sub 5"), Command::Sub(5));
+        assert_eq!(parse_command("clock"), Command::Clocks);
+        assert_eq!(parse_command("clock new East Party"), Command::ClockNew("East Party".to_string()));
+        assert_eq!(parse_command("clock use west"), Command::ClockUse("west".to_string()));
+        assert_eq!(parse_command("clock new"), Command::Unknown("clock new".to_string()));
+        assert_eq!(parse_command("turn"), Command::Turn);
+        assert_eq!(parse_command("watch"), Command::Watch);
+        assert_eq!(parse_command("goto Dawn"), Command::Goto("dawn".to_string()));
+        assert_eq!(parse_command("day 5"), Command::SetDay { day: 5, relative: false });
+        assert_eq!(parse_command("day +2"), Command::SetDay { day: 2, relative: true });
+        assert_eq!(parse_command("goto-day -1"), Command::SetDay { day: -1, relative: true });
+        assert_eq!(parse_command("day"), Command::Unknown("day".to_string()));
+        assert_eq!(parse_command("day five"), Command::Unknown("day five".to_string()));
+        assert_eq!(parse_command("lookup Goblin Ambush"), Command::Lookup("goblin ambush".to_string()));
+        assert_eq!(parse_command("lookup"), Command::Unknown("lookup".to_string()));
+        assert_eq!(parse_command("ev"), Command::Ev(None));
+        assert_eq!(parse_command("ev Wilderness Encounters"), Command::Ev(Some("Wilderness Encounters".to_string())));
+        assert_eq!(parse_command("describe"), Command::Describe(None));
+        assert_eq!(parse_command("describe 2d6"), Command::Describe(Some("2d6".to_string())));
+        assert_eq!(parse_command("check 1d6<=2"), Command::Check("1d6<=2".to_string(), false));
+        assert_eq!(parse_command("check 1d6 = 1 then roll"), Command::Check("1d6=1".to_string(), true));
+        assert_eq!(parse_command("check"), Command::Unknown("check".to_string()));
+        assert_eq!(parse_command("prompt ] "), Command::Prompt("] ".to_string()));
+        assert_eq!(parse_command("prompt"), Command::Prompt("".to_string()));
+        assert_eq!(parse_command("help"), Command::Help(None));
+        assert_eq!(parse_command("help Roll"), Command::Help(Some("roll".to_string())));
         assert_eq!(parse_command("unknown command"), Command::Unknown("unknown command".to_string()));
     }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("  roll   wilderness encounters "), ["roll", "wilderness", "encounters"]);
+        assert_eq!(tokenize(r#"addrow "Goblin King" 12"#), ["addrow", "Goblin King", "12"]);
+        assert_eq!(tokenize(r#"use "two  spaces""#), ["use", "two  spaces"]);
+        assert_eq!(tokenize(r#"prompt """#), ["prompt", ""]);
+        assert!(tokenize("   ").is_empty());
+    }
+
+    #[test]
+    fn test_tokenize_unterminated_quote() {
+        assert_eq!(tokenize(r#"lookup "Goblin Ambush"#), ["lookup", "Goblin Ambush"]);
+        assert_eq!(tokenize(r#"use "city  events  "#), ["use", "city  events  "]);
+    }
+
+    #[test]
+    fn test_parse_quoted_arguments() {
+        assert_eq!(
+            parse_command(r#"roll "fight with" with 1d6"#),
+            Command::RollTable(Some("fight with".to_string()), Some("1d6".to_string()), vec![])
+        );
+        assert_eq!(parse_command(r#"lookup "Goblin  King""#), Command::Lookup("goblin  king".to_string()));
+        assert_eq!(parse_command(r#"prompt "] ""#), Command::Prompt("] ".to_string()));
+    }
+
+    #[test]
+    fn test_parse_roll_modifiers() {
+        assert_eq!(
+            parse_command("roll monsters :Night"),
+            Command::RollTable(Some("monsters".to_string()), None, vec!["night".to_string()])
+        );
+        assert_eq!(
+            parse_command("roll :night with 1d6 :cave"),
+            Command::RollTable(
+                None,
+                Some("1d6".to_string()),
+                vec!["night".to_string(), "cave".to_string()]
+            )
+        );
+        assert_eq!(
+            parse_command("roll :"),
+            Command::RollTable(Some(":".to_string()), None, vec![])
+        );
+    }
+
+    #[test]
+    fn test_parse_roll_many() {
+        assert_eq!(
+            parse_command("roll 3 monsters"),
+            Command::RollMany { times: 3, target: Some("monsters".to_string()), unique: false }
+        );
+        assert_eq!(
+            parse_command("roll 5 Wilderness Encounters UNIQUE"),
+            Command::RollMany {
+                times: 5,
+                target: Some("Wilderness Encounters".to_string()),
+                unique: true
+            }
+        );
+        assert_eq!(
+            parse_command("roll 2 unique"),
+            Command::RollMany { times: 2, target: None, unique: true }
+        );
+        assert_eq!(
+            parse_command("roll 0 monsters"),
+            Command::RollTable(Some("0 monsters".to_string()), None, vec![])
+        );
+        assert_eq!(
+            parse_command("roll 3 monsters :night"),
+            Command::RollTable(Some("3 monsters".to_string()), None, vec!["night".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_roll_with_dice_override() {
+        assert_eq!(
+            parse_command("roll monsters with 1d6"),
+            Command::RollTable(Some("monsters".to_string()), Some("1d6".to_string()), vec![])
+        );
+        assert_eq!(
+            parse_command("roll Wilderness Encounters WITH 1D6"),
+            Command::RollTable(Some("Wilderness Encounters".to_string()), Some("1d6".to_string()), vec![])
+        );
+        assert_eq!(parse_command("roll with 1d6"), Command::RollTable(None, Some("1d6".to_string()), vec![]));
+        assert_eq!(
+            parse_command("roll fight with dragons"),
+            Command::RollTable(Some("fight with dragons".to_string()), None, vec![])
+        );
+    }
 }
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+// Settings that live alongside the tables in the config file. Every section and key is
+// optional, so a file containing only `[[table]]` entries is still a valid config.
+//
+// Example TOML representation:
+// ```toml
+// default = "wilderness encounters"
+// max_table_depth = 5
+//
+// [ui]
+// prompt = "] "
+// quiet = true
+// autoscroll = false
+//
+// [window]
+// title = "Wayline: Caverns of Thracia"
+// theme = "Tokyo Night"
+//
+// [matching]
+// case_sensitive = true
+//
+// [startup]
+// commands = ["use wilderness encounters", "add 480"]
+//
+// [time.anchors]
+// dawn = "05:30"
+// second-breakfast = "11:00"
+//
+// [time.bands]
+// day = "06:00"
+// night = "20:00"
+//
+// [keybindings]
+// "ctrl+r" = "roll wilderness encounters"
+// "f1" = ""
+//
+// [aliases]
+// w = "roll wilderness encounters"
+// ```
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    pub default: Option<String>, // Table selected at startup
+    pub max_table_depth: Option<usize>, // How many tables deep `gen` follows subtables
+    #[serde(default)]
+    pub ui: UiConfig,
+    #[serde(default)]
+    pub window: WindowConfig,
+    #[serde(default)]
+    pub matching: MatchingConfig,
+    #[serde(default)]
+    pub startup: StartupConfig,
+    #[serde(default)]
+    pub time: TimeConfig,
+    // Key combination (see `normalize_key`) to the command it runs; an empty command unbinds
+    // the key
+    #[serde(default)]
+    pub keybindings: HashMap<String, String>,
+    // Word typed as a command to what it stands for; a table's own `aliases` win while it's
+    // current
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct UiConfig {
+    pub prompt: Option<String>, // Prefix for echoed commands
+    // Leave out informational lines while starting up; results and errors still show
+    #[serde(default)]
+    pub quiet: bool,
+    // Whether the scrollback jumps to each new line; on unless set to false
+    pub autoscroll: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WindowConfig {
+    pub title: Option<String>,
+    pub theme: Option<String>, // Name of a built-in iced theme, e.g. "Dracula"
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MatchingConfig {
+    // Table names match case-insensitively unless this is set
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StartupConfig {
+    // Run in order once the tables have loaded; `#` lines are comments
+    #[serde(default)]
+    pub commands: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TimeConfig {
+    // Named times of day ("HH:MM") for `goto`, added to or overriding the built-in ones
+    #[serde(default)]
+    pub anchors: HashMap<String, String>,
+    // Labels for parts of the day, each running from its start ("HH:MM") to the next one's;
+    // replaces the built-in bands when given
+    #[serde(default)]
+    pub bands: HashMap<String, String>,
+}
+
+pub fn parse_config(toml_str: &str) -> Result<Config, toml::de::Error> {
+    toml::from_str(toml_str)
+}
+
+const MODIFIERS: [&str; 4] = ["ctrl", "alt", "shift", "logo"];
+
+/// Canonical form of a key combination such as "Shift+Ctrl+R": lowercase, modifiers in the
+/// order ctrl, alt, shift, logo, then the key, joined by `+`.
+pub fn normalize_key(combo: &str) -> String {
+    let mut parts: Vec<String> = combo
+        .split('+')
+        .map(|part| match part.trim().to_lowercase().as_str() {
+            "control" => "ctrl".to_string(),
+            "cmd" | "super" | "meta" => "logo".to_string(),
+            part => part.to_string(),
+        })
+        .collect();
+    let key = parts.pop().unwrap_or_default();
+    parts.sort_by_key(|part| MODIFIERS.iter().position(|m| m == part));
+    parts.dedup();
+    parts.push(key);
+    parts.join("+")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ui_section() {
+        let toml_str = r#"
+            [ui]
+            prompt = "] "
+
+            [[table]]
+            name = "Monsters"
+            dice = "1d6"
+            rows = []
+        "#;
+        let config = parse_config(toml_str).unwrap();
+        assert_eq!(config.ui.prompt.as_deref(), Some("] "));
+
+        let config = parse_config("[ui]\nprompt = \"\"").unwrap();
+        assert_eq!(config.ui.prompt.as_deref(), Some(""));
+
+        let config = parse_config("").unwrap();
+        assert!(config.ui.prompt.is_none());
+        assert!(!config.ui.quiet);
+        assert!(parse_config("[ui]\nquiet = true").unwrap().ui.quiet);
+    }
+
+    #[test]
+    fn test_parse_window_section() {
+        let config = parse_config("[window]\ntitle = \"Thracia\"\ntheme = \"Dracula\"").unwrap();
+        assert_eq!(config.window.title.as_deref(), Some("Thracia"));
+        assert_eq!(config.window.theme.as_deref(), Some("Dracula"));
+
+        let config = parse_config("").unwrap();
+        assert!(config.window.title.is_none());
+        assert!(config.window.theme.is_none());
+    }
+
+    #[test]
+    fn test_parse_default_table() {
+        let config = parse_config("default = \"Monsters\"").unwrap();
+        assert_eq!(config.default.as_deref(), Some("Monsters"));
+        assert_eq!(parse_config("").unwrap().default, None);
+
+        assert_eq!(parse_config("max_table_depth = 3").unwrap().max_table_depth, Some(3));
+        assert!(parse_config("max_table_depth = -1").is_err());
+    }
+
+    #[test]
+    fn test_parse_matching_section() {
+        let config = parse_config("[matching]\ncase_sensitive = true").unwrap();
+        assert!(config.matching.case_sensitive);
+
+        let config = parse_config("").unwrap();
+        assert!(!config.matching.case_sensitive);
+    }
+
+    #[test]
+    fn test_parse_time_anchors() {
+        let toml_str = r#"
+            [time.anchors]
+            dawn = "05:30"
+            second-breakfast = "11:00"
+        "#;
+        let config = parse_config(toml_str).unwrap();
+        assert_eq!(config.time.anchors["dawn"], "05:30");
+        assert_eq!(config.time.anchors["second-breakfast"], "11:00");
+        assert!(config.time.bands.is_empty());
+
+        let config = parse_config("[time.bands]\nday = \"06:00\"\nnight = \"20:00\"").unwrap();
+        assert_eq!(config.time.bands["night"], "20:00");
+    }
+
+    #[test]
+    fn test_parse_keybindings() {
+        let toml_str = r#"
+            [keybindings]
+            "Ctrl+R" = "roll monsters"
+            F1 = ""
+        "#;
+        let config = parse_config(toml_str).unwrap();
+        assert_eq!(config.keybindings["Ctrl+R"], "roll monsters");
+        assert_eq!(config.keybindings["F1"], "");
+        assert!(parse_config("").unwrap().keybindings.is_empty());
+
+        assert_eq!(normalize_key("Ctrl+R"), "ctrl+r");
+        assert_eq!(normalize_key("shift + control + F5"), "ctrl+shift+f5");
+        assert_eq!(normalize_key("F1"), "f1");
+    }
+
+    #[test]
+    fn test_parse_aliases() {
+        let config = parse_config("[aliases]\nw = \"roll wilderness\"").unwrap();
+        assert_eq!(config.aliases["w"], "roll wilderness");
+        assert!(parse_config("").unwrap().aliases.is_empty());
+    }
+
+    #[test]
+    fn test_parse_startup_section() {
+        let toml_str = r##"
+            [startup]
+            commands = ["use wilderness", "# set the scene", "add 480"]
+        "##;
+        let config = parse_config(toml_str).unwrap();
+        assert_eq!(config.startup.commands, ["use wilderness", "# set the scene", "add 480"]);
+
+        let config = parse_config("").unwrap();
+        assert!(config.startup.commands.is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+// Watches the config file so edits to tables show up without restarting.
+//
+// notify delivers events on its own thread. A second thread collapses bursts of events into a
+// single change, since editors often write a file in several steps (truncate, write, rename),
+// and the result is forwarded to the subscription's async stream.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use iced::Subscription;
+use iced::futures::channel::mpsc as async_mpsc;
+use iced::futures::{SinkExt, StreamExt};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tracing::error;
+
+use crate::Message;
+
+/// How long the file has to stay quiet before a change is reported.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+pub fn subscription(path: &Path) -> Subscription<Message> {
+    let path = path.to_path_buf();
+    Subscription::run_with_id(
+        path.clone(),
+        iced::stream::channel(1, move |mut output| async move {
+            let (changes_tx, mut changes) = async_mpsc::unbounded();
+            // The watcher stops when dropped, so it has to live as long as the stream.
+            let _watcher = match watch(&path, changes_tx) {
+                Ok(watcher) => watcher,
+                Err(e) => {
+                    error!("Failed to watch {}: {}", path.display(), e);
+                    return;
+                }
+            };
+
+            while changes.next().await.is_some() {
+                if output.send(Message::ConfigChanged).await.is_err() {
+                    break;
+                }
+            }
+        }),
+    )
+}
+
+// Watches the file's directory rather than the file itself, so saves that replace the file
+// with a new one are still seen.
+fn watch(
+    path: &Path,
+    changes: async_mpsc::UnboundedSender<()>,
+) -> notify::Result<RecommendedWatcher> {
+    let file_name = path.file_name().map(ToOwned::to_owned);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+
+    let (events_tx, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        if let Ok(event) = event
+            && (event.kind.is_create() || event.kind.is_modify())
+            && event
+                .paths
+                .iter()
+                .any(|changed| changed.file_name() == file_name.as_deref())
+        {
+            let _ = events_tx.send(());
+        }
+    })?;
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    std::thread::spawn(move || debounce(events, changes));
+    Ok(watcher)
+}
+
+fn debounce(events: mpsc::Receiver<()>, changes: async_mpsc::UnboundedSender<()>) {
+    while events.recv().is_ok() {
+        while events.recv_timeout(DEBOUNCE).is_ok() {}
+        if changes.unbounded_send(()).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+
+use serde::Serialize;
+
+// Structured records of what happened in a session, for piping into other tools. These are
+// written alongside the scrollback, one JSON object per line, when `--log <path>` is given.
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event")]
+pub enum WaylineEvent {
+    RollMade {
+        table: Option<String>, // `None` for plain dice rolls
+        dice: String,
+        roll: u32,
+        result: Option<String>,
+    },
+    CheckMade {
+        check: String,
+        roll: u32,
+        success: bool,
+    },
+    TableSwitched {
+        table: String,
+    },
+    TimeAdvanced {
+        clock: String,
+        minutes: i64, // negative when time was subtracted
+        time: u32,
+    },
+}
+
+/// Appends events as JSON Lines to a file.
+pub struct EventLog {
+    writer: BufWriter<File>,
+}
+
+impl std::fmt::Debug for EventLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventLog").finish_non_exhaustive()
+    }
+}
+
+impl EventLog {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open event log {}: {}", path, e))?;
+        Ok(EventLog {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn write(&mut self, event: &WaylineEvent) -> Result<(), String> {
+        let json = serde_json::to_string(event)
+            .map_err(|e| format!("Failed to serialize event: {}", e))?;
+        // Flushed per event so a crash or a `tail -f` never misses one.
+        writeln!(self.writer, "{}", json)
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("Failed to write event: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_json() {
+        let event = WaylineEvent::TableSwitched {
+            table: "Monsters".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"event":"TableSwitched","table":"Monsters"}"#
+        );
+    }
+}
@@ -0,0 +1,550 @@
+use wayline::api;
+
+// Help text for every command lives here so the `help` overview and `help <command>`
+// detail views are generated from the same source and can't drift apart.
+
+pub struct HelpEntry {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub summary: &'static str,
+    pub details: &'static [&'static str],
+    pub example: &'static str,
+}
+
+pub const HELP_ENTRIES: &[HelpEntry] = &[
+    HelpEntry {
+        name: "use",
+        usage: "use <table name | number>",
+        summary: "Select a table as current",
+        details: &[
+            "Later `roll` and `list` commands without a table name use the current table.",
+            "Names match regardless of case unless `case_sensitive` is set under `[matching]`.",
+            "A number picks that table from the last `list` of tables.",
+            "Prints the table's description, if it has one.",
+        ],
+        example: "use wilderness encounters",
+    },
+    HelpEntry {
+        name: "back",
+        usage: "back",
+        summary: "Switch back to the previously selected table",
+        details: &[
+            "Running it again switches forward, so it flips between two tables.",
+            "`prev` does the same.",
+        ],
+        example: "back",
+    },
+    HelpEntry {
+        name: "roll",
+        usage: "roll [table name | dice] [with <dice>] [:modifier ...]",
+        summary: "Roll on the current table or a table with [table name]",
+        details: &[
+            "With no argument, rolls the current table's dice and prints the matching entry.",
+            "Dice notation such as `1d20` is rolled directly unless a table has that name.",
+            "`with <dice>` rolls the table on those dice instead of its own.",
+            "`<table name>.<column>` shows one column of a table with several.",
+            "`:<name>` adds the table's named modifier (from `[table.modifiers]`) to the roll.",
+            "`roll <times> [table name] [unique]` rolls a table that many times, at most 100.",
+            "With `unique`, entries that already came up are rerolled while new ones turn up.",
+            "`--json` (or `roll-json`) prints the table, roll and entry as one JSON object.",
+            "With `<times>` too, it prints a JSON array of that many rolls.",
+        ],
+        example: "roll city events",
+    },
+    HelpEntry {
+        name: "peek",
+        usage: "peek [table name]",
+        summary: "Roll a table just to see what would come up",
+        details: &["The result isn't kept for `last`, shown in the roll log or logged."],
+        example: "peek wilderness encounters",
+    },
+    HelpEntry {
+        name: "force",
+        usage: "force <table name> <row>",
+        summary: "Show a table's entry as if it had been rolled",
+        details: &[
+            "Rows count from 1 in the order the table lists them.",
+            "Nothing is rolled, so the result isn't kept for `last` or logged.",
+        ],
+        example: "force wilderness encounters 3",
+    },
+    HelpEntry {
+        name: "gen",
+        usage: "gen [table name]",
+        summary: "Roll a table and follow its entries' subtables",
+        details: &[
+            "An entry with `subtable = \"<table name>\"` rolls that table next.",
+            "Each result is indented under the one that led to it.",
+            "Stops after `max_table_depth` tables (10 unless set in the config).",
+        ],
+        example: "gen wilderness encounters",
+    },
+    HelpEntry {
+        name: "sample",
+        usage: "sample <times> [table name]",
+        summary: "Roll a table many times and count each result",
+        details: &[
+            "Results are listed most frequent first, with their share of the rolls.",
+            "At most 100000 rolls.",
+        ],
+        example: "sample 100 wilderness encounters",
+    },
+    HelpEntry {
+        name: "rolluntil",
+        usage: "rolluntil <entry name> [max attempts]",
+        summary: "Roll the current table until an entry comes up",
+        details: &[
+            "Prints how many rolls it took.",
+            "Gives up after the max attempts, at most 1000 (the default).",
+        ],
+        example: "rolluntil dragon sighting 50",
+    },
+    HelpEntry {
+        name: "dice",
+        usage: "dice <notation> [min <n>]",
+        summary: "Roll custom dice (e.g., '2d6')",
+        details: &[
+            "Notation is <count>d<sides>; the count may be omitted for a single die.",
+            "`d66` and `d666` read each d6 as a digit (11-66), for tables numbered that way.",
+            "`min <n>` raises a lower result to n (it doesn't reroll) and shows the raw roll.",
+            "`dice help` lists every supported notation.",
+        ],
+        example: "dice 3d6",
+    },
+    HelpEntry {
+        name: "oppose",
+        usage: "oppose <dice> vs <dice>",
+        summary: "Roll two sides of an opposed check and show who wins",
+        details: &["Each side may add or subtract a number, e.g. `1d8+1`."],
+        example: "oppose 2d6 vs 1d8+1",
+    },
+    HelpEntry {
+        name: "sroll",
+        usage: "sroll <seed> <dice>",
+        summary: "Roll dice reproducibly from a seed",
+        details: &["The same seed and dice always give the same result, e.g. to share a roll."],
+        example: "sroll 42 3d6",
+    },
+    HelpEntry {
+        name: "avg",
+        usage: "avg <times> <dice>",
+        summary: "Roll dice many times and show the average, lowest and highest",
+        details: &["At most 1000000 rolls, and 10000000 dice across all of them."],
+        example: "avg 1000 2d6",
+    },
+    HelpEntry {
+        name: "total",
+        usage: "total <dice | reset>",
+        summary: "Roll dice and add them to a running subtotal",
+        details: &[
+            "`+<dice>` is shorthand, e.g. `+2d6`.",
+            "`total reset` sets the subtotal back to zero.",
+        ],
+        example: "+2d6",
+    },
+    HelpEntry {
+        name: "list",
+        usage: "list [table name | all]",
+        summary: "List the current table entries, or all tables if current table is unset",
+        details: &[
+            "`list all` lists every loaded table even when one is selected.",
+            "Long listings are shown a page at a time; use `more` for the next page.",
+        ],
+        example: "list all",
+    },
+    HelpEntry {
+        name: "tables",
+        usage: "tables",
+        summary: "List loaded tables with the file each came from",
+        details: &["Tables from files included by the config show that file's path."],
+        example: "tables",
+    },
+    HelpEntry {
+        name: "findtable",
+        usage: "findtable [dice]",
+        summary: "List the tables rolled on some dice",
+        details: &[
+            "Dice match however they're written, so `d20` finds tables rolled on `1d20`.",
+            "With no dice, lists every table grouped by its dice.",
+        ],
+        example: "findtable 2d6",
+    },
+    HelpEntry {
+        name: "more",
+        usage: "more",
+        summary: "Show the next page of the last listing",
+        details: &[],
+        example: "more",
+    },
+    HelpEntry {
+        name: "last",
+        usage: "last",
+        summary: "Show the most recent roll or check result again",
+        details: &[],
+        example: "last",
+    },
+    HelpEntry {
+        name: "rolls",
+        usage: "rolls",
+        summary: "List the last 20 rolls, newest first",
+        details: &["`history rolls` does the same."],
+        example: "rolls",
+    },
+    HelpEntry {
+        name: "lookup",
+        usage: "lookup <entry name>",
+        summary: "Show which rolls produce an entry",
+        details: &[
+            "Matches entry names case-insensitively across every loaded table.",
+            "The current table's match is listed first.",
+        ],
+        example: "lookup goblin ambush",
+    },
+    HelpEntry {
+        name: "ev",
+        usage: "ev [table name]",
+        summary: "Show the expected severity of a roll on a table",
+        details: &[
+            "Rows count as severities 1, 2, 3... in the order they're listed.",
+            "Warns when some dice totals match no row, since those are left out.",
+        ],
+        example: "ev wilderness encounters",
+    },
+    HelpEntry {
+        name: "compare",
+        usage: "compare <table> <table>",
+        summary: "Show each entry's chance on two tables side by side",
+        details: &[
+            "Entries with the same name share a row; `-` means a table doesn't have it.",
+            "Each table uses its own dice.",
+            "Separate names with spaces using `vs`, e.g. `compare city events vs wilderness`.",
+        ],
+        example: "compare monsters beasts",
+    },
+    HelpEntry {
+        name: "odds",
+        usage: "odds [table name]",
+        summary: "List a table's entries from most to least likely, with a bar for each",
+        details: &[
+            "The most likely entry gets the longest bar; the rest are scaled to match.",
+            "Entries that can never come up are listed last with an empty bar.",
+        ],
+        example: "odds wilderness encounters",
+    },
+    HelpEntry {
+        name: "whatif",
+        usage: "whatif [table name] <+N | -N>",
+        summary: "Show which entries a bonus or penalty to the roll brings in or pushes out",
+        details: &[
+            "Nothing is rolled; the table's dice range is shifted by the modifier.",
+            "Also lists any shifted results that no entry covers.",
+        ],
+        example: "whatif wilderness encounters +2",
+    },
+    HelpEntry {
+        name: "matrix",
+        usage: "matrix <table> <table>",
+        summary: "Roll two tables and show their results together, e.g. terrain and creature",
+        details: &[
+            "Each table is rolled on its own dice, as `roll` would.",
+            "Separate names with spaces using `+`, e.g. `matrix hex terrain + wandering monsters`.",
+        ],
+        example: "matrix terrain creatures",
+    },
+    HelpEntry {
+        name: "describe",
+        usage: "describe [table name | dice]",
+        summary: "Describe in words which results a table's dice favour",
+        details: &["Defaults to the current table's dice."],
+        example: "describe 2d6",
+    },
+    HelpEntry {
+        name: "check",
+        usage: "check <dice><op><threshold> [then roll]",
+        summary: "Roll dice against a threshold, e.g. for encounter checks",
+        details: &[
+            "The comparison may be <=, >=, <, > or =.",
+            "With `then roll`, a successful check rolls the current table.",
+        ],
+        example: "check 1d6<=1 then roll",
+    },
+    HelpEntry {
+        name: "time",
+        usage: "time",
+        summary: "Show current in-game time",
+        details: &[
+            "Also shows how many turns and watches have passed.",
+            "Times are labelled with the part of the day; see `[time.bands]` in the config.",
+        ],
+        example: "time",
+    },
+    HelpEntry {
+        name: "add",
+        usage: "add <minutes | dice>",
+        summary: "Add minutes to in-game time",
+        details: &[
+            "At most a year's worth (525600 minutes) at a time.",
+            "Dice such as `1d10` or `2d6+3` are rolled for the number of minutes.",
+        ],
+        example: "add 30",
+    },
+    HelpEntry {
+        name: "sub",
+        usage: "sub <minutes>",
+        summary: "Subtract minutes from in-game time",
+        details: &["The clock stops at 00:00."],
+        example: "sub 10",
+    },
+    HelpEntry {
+        name: "travel",
+        usage: "travel <minutes> [check]",
+        summary: "Add minutes to in-game time, then roll the current table for an encounter",
+        details: &[
+            "With a check such as `1d6<=1`, the table is only rolled if the check succeeds.",
+            "Without a current table, only the time advances.",
+        ],
+        example: "travel 240 1d6<=1",
+    },
+    HelpEntry {
+        name: "goto",
+        usage: "goto <time of day>",
+        summary: "Advance to the next dawn, noon, dusk or midnight",
+        details: &[
+            "Moves into the next day if that time has already passed today.",
+            "More times of day can be named under `[time.anchors]` in the config file.",
+        ],
+        example: "goto dawn",
+    },
+    HelpEntry {
+        name: "day",
+        usage: "day <N | +N | -N>",
+        summary: "Jump to day N, or N days on or back, keeping the time of day",
+        details: &["Days count from 1, the day the clock starts on; it can't go back before it."],
+        example: "day +2",
+    },
+    HelpEntry {
+        name: "clock",
+        usage: "clock [new <name> | use <name>]",
+        summary: "List, create or switch in-game clocks",
+        details: &[
+            "Each clock keeps its own time, e.g. one per party.",
+            "`add`, `sub`, `time`, `turn` and `watch` use the active clock.",
+            "The clock you start with is called `default`.",
+        ],
+        example: "clock new east party",
+    },
+    HelpEntry {
+        name: "turn",
+        usage: "turn",
+        summary: "Advance one 10-minute dungeon turn",
+        details: &["Also adds 10 minutes to in-game time."],
+        example: "turn",
+    },
+    HelpEntry {
+        name: "watch",
+        usage: "watch",
+        summary: "Advance one 4-hour watch",
+        details: &["Counts as 24 turns and adds 240 minutes to in-game time."],
+        example: "watch",
+    },
+    HelpEntry {
+        name: "undo",
+        usage: "undo",
+        summary: "Undo the last time change or table switch",
+        details: &[
+            "Rolls and other commands that don't change the time or table aren't undone.",
+            "Remembers the last 100 changes.",
+        ],
+        example: "undo",
+    },
+    HelpEntry {
+        name: "redo",
+        usage: "redo",
+        summary: "Redo the last change undone with `undo`",
+        details: &["Making a new change forgets anything left to redo."],
+        example: "redo",
+    },
+    HelpEntry {
+        name: "reload",
+        usage: "reload",
+        summary: "Reload tables from the config file",
+        details: &[
+            "Files listed in the config's `include = [...]` are loaded too.",
+            "The config is also reloaded automatically whenever the file changes.",
+            "After starting with `--no-config`, only `reload` loads tables.",
+            "Tables in the `--tables-dir` folder, one `.toml` or `.json` file each, reload too.",
+            "The current table stays selected if it still exists.",
+        ],
+        example: "reload",
+    },
+    HelpEntry {
+        name: "edit",
+        usage: "edit",
+        summary: "Open the config file in your editor, then reload it",
+        details: &[
+            "Uses the editor named by the EDITOR environment variable, or `vi` if it's unset.",
+            "Tables are only reloaded if the editor exits successfully.",
+        ],
+        example: "edit",
+    },
+    HelpEntry {
+        name: "prompt",
+        usage: "prompt [text]",
+        summary: "Change the prefix shown before echoed commands",
+        details: &[
+            "With no text, commands are echoed without a prefix.",
+            "The default can be set with `prompt` under `[ui]` in the config file.",
+        ],
+        example: "prompt ] ",
+    },
+    HelpEntry {
+        name: "clear",
+        usage: "clear",
+        summary: "Clear the scrollback",
+        details: &[],
+        example: "clear",
+    },
+    HelpEntry {
+        name: "reset",
+        usage: "reset",
+        summary: "Start the session over without reloading tables",
+        details: &[
+            "Every clock goes back to 00:00 on day 1 and the turn count to 0.",
+            "The subtotal, the list from `rolls` and undo history are cleared.",
+            "The current table goes back to the config's `default`, as at startup.",
+        ],
+        example: "reset",
+    },
+    HelpEntry {
+        name: "summary",
+        usage: "summary",
+        summary: "Show the loaded tables, current table, time and aliases at a glance",
+        details: &[],
+        example: "summary",
+    },
+    HelpEntry {
+        name: "log",
+        usage: "log",
+        summary: "Show or hide the roll log panel",
+        details: &["The roll log lists roll results only, below the scrollback."],
+        example: "log",
+    },
+    HelpEntry {
+        name: "top",
+        usage: "top",
+        summary: "Scroll to the start of the scrollback",
+        details: &[],
+        example: "top",
+    },
+    HelpEntry {
+        name: "bottom",
+        usage: "bottom",
+        summary: "Scroll to the newest line of the scrollback",
+        details: &[],
+        example: "bottom",
+    },
+    HelpEntry {
+        name: "autoscroll",
+        usage: "autoscroll",
+        summary: "Turn following new lines in the scrollback on or off",
+        details: &[
+            "While it's off, new lines don't move the scrollback; use `bottom` to catch up.",
+            "Set `autoscroll = false` under `[ui]` in the config to start with it off.",
+        ],
+        example: "autoscroll",
+    },
+    HelpEntry {
+        name: "version",
+        usage: "version",
+        summary: "Show the wayline version",
+        details: &["`about` does the same."],
+        example: "version",
+    },
+    HelpEntry {
+        name: "help",
+        usage: "help [command]",
+        summary: "Show this help message",
+        details: &[
+            "With a command name, shows that command's full syntax and an example.",
+            "Shortcuts: F1 help, F5 reload, Ctrl+R roll, Ctrl+L clear.",
+            "Up and Down step through the commands entered before.",
+            "Keys can be rebound under `[keybindings]` in the config file.",
+        ],
+        example: "help roll",
+    },
+];
+
+pub fn find(topic: &str) -> Option<&'static HelpEntry> {
+    HELP_ENTRIES.iter().find(|entry| entry.name == topic)
+}
+
+/// The known command whose name is the fewest edits away from `topic`, if any is close enough
+/// to plausibly be a typo.
+pub fn closest(topic: &str) -> Option<&'static str> {
+    HELP_ENTRIES
+        .iter()
+        .map(|entry| (edit_distance(topic, entry.name), entry.name))
+        .filter(|(distance, name)| *distance <= name.len().max(topic.len()) / 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+pub fn overview() -> Vec<String> {
+    let mut lines = vec!["Available commands:".to_string()];
+    for entry in HELP_ENTRIES {
+        lines.push(format!("- {} : {}", entry.usage, entry.summary));
+    }
+    lines
+}
+
+pub fn detail(entry: &HelpEntry) -> Vec<String> {
+    let mut lines = vec![format!("Usage: {}", entry.usage), entry.summary.to_string()];
+    lines.extend(entry.details.iter().map(|line| line.to_string()));
+    lines.push(format!("Example: {}", entry.example));
+    lines
+}
+
+pub fn dice_notations() -> Vec<String> {
+    let mut lines = vec!["Dice notation:".to_string()];
+    for (form, example, description) in api::DICE_NOTATIONS {
+        lines.push(format!("- {} : {} (e.g. {})", form, description, example));
+    }
+    lines
+}
+
+// Levenshtein distance over chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            curr.push((prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1));
+        }
+        prev = curr;
+    }
+    prev[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_closest() {
+        assert_eq!(find("roll").map(|e| e.name), Some("roll"));
+        assert!(find("rol").is_none());
+        assert_eq!(closest("rol"), Some("roll"));
+        assert_eq!(closest("lsit"), Some("list"));
+        assert_eq!(closest("xyzzy"), None);
+    }
+
+    #[test]
+    fn test_dice_notations() {
+        let text = dice_notations().join("\n");
+        for form in ["2d6", "d20", "d66", "d666", "weighted"] {
+            assert!(text.contains(form), "{}", form);
+        }
+    }
+}
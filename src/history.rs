@@ -0,0 +1,79 @@
+// Undo history for changes to the clocks and the current table. Commands that don't change
+// either, like rolls and listings, aren't recorded.
+
+/// Oldest actions are forgotten beyond this many.
+const MAX_ACTIONS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    TimeDelta { clock: String, before: u32, after: u32, turns: u32 }, // minutes; turns passed
+    TableSwitch { before: Option<String>, after: String },
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    actions: Vec<Action>,
+    index: usize, // Actions before this are applied; from here on they've been undone
+}
+
+impl History {
+    /// Records a new action, dropping any that were undone since they can't be redone now.
+    pub fn record(&mut self, action: Action) {
+        self.actions.truncate(self.index);
+        self.actions.push(action);
+        if self.actions.len() > MAX_ACTIONS {
+            self.actions.remove(0);
+        }
+        self.index = self.actions.len();
+    }
+
+    /// The most recently applied action, now marked as undone.
+    pub fn undo(&mut self) -> Option<Action> {
+        self.index = self.index.checked_sub(1)?;
+        Some(self.actions[self.index].clone())
+    }
+
+    /// The most recently undone action, now marked as applied again.
+    pub fn redo(&mut self) -> Option<Action> {
+        let action = self.actions.get(self.index)?.clone();
+        self.index += 1;
+        Some(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn switch(after: &str) -> Action {
+        Action::TableSwitch { before: None, after: after.to_string() }
+    }
+
+    #[test]
+    fn test_record_after_undo_drops_redo() {
+        let mut history = History::default();
+        assert_eq!(history.undo(), None);
+        history.record(switch("a"));
+        history.record(switch("b"));
+        assert_eq!(history.undo(), Some(switch("b")));
+        history.record(switch("c"));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.undo(), Some(switch("c")));
+        assert_eq!(history.undo(), Some(switch("a")));
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.redo(), Some(switch("a")));
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut history = History::default();
+        for n in 0..MAX_ACTIONS + 5 {
+            history.record(switch(&n.to_string()));
+        }
+        let mut undone = 0;
+        while history.undo().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, MAX_ACTIONS);
+    }
+}
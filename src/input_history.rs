@@ -0,0 +1,71 @@
+// Commands entered in the input box, kept across runs like a shell's history file: one
+// command per line, oldest first.
+
+/// Only the most recent commands are kept.
+pub const MAX_ENTRIES: usize = 500;
+
+/// Appends `line` unless it repeats the latest entry, forgetting the oldest beyond
+/// `MAX_ENTRIES`.
+pub fn push(history: &mut Vec<String>, line: &str) {
+    if history.last().is_some_and(|last| last == line) {
+        return;
+    }
+    history.push(line.to_string());
+    if history.len() > MAX_ENTRIES {
+        history.drain(..history.len() - MAX_ENTRIES);
+    }
+}
+
+/// Reads the history saved at `path`. A missing file, as on the first run, is an empty history.
+pub fn load(path: &str) -> Result<Vec<String>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read history file {}: {}", path, e)),
+    };
+    let mut history = Vec::new();
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        push(&mut history, line);
+    }
+    Ok(history)
+}
+
+pub fn save(path: &str, history: &[String]) -> Result<(), String> {
+    let mut contents = history.join("\n");
+    contents.push('\n');
+    std::fs::write(path, contents)
+        .map_err(|e| format!("Failed to write history file {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_collapses_repeats_and_caps() {
+        let mut history = Vec::new();
+        push(&mut history, "roll");
+        push(&mut history, "roll");
+        push(&mut history, "add 10");
+        push(&mut history, "roll");
+        assert_eq!(history, ["roll", "add 10", "roll"]);
+
+        for n in 0..MAX_ENTRIES {
+            push(&mut history, &format!("add {}", n));
+        }
+        assert_eq!(history.len(), MAX_ENTRIES);
+        assert_eq!(history[0], "add 0");
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = std::env::temp_dir().join(format!("wayline-history-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        let history = vec!["use monsters".to_string(), "roll".to_string()];
+        save(path, &history).unwrap();
+        let loaded = load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded, history);
+        assert_eq!(load(path), Ok(Vec::new()));
+    }
+}
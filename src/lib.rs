@@ -0,0 +1,9 @@
+//! The table rolling core of wayline: table definitions, dice and table rolls, and the command
+//! parser. The `wayline` binary is a GUI and CLI shell around it.
+
+pub mod api;
+pub mod command;
+pub mod table;
+
+pub use api::{parse_tables, roll, roll_on};
+pub use command::parse_command;
@@ -1,30 +1,202 @@
-mod api;
-mod command;
-mod table;
+mod config;
+mod config_watch;
+mod event;
+mod help;
+mod history;
+mod input_history;
+mod scrollback;
+mod session;
+mod transcript;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-use iced::widget::{column, text_editor, text_editor::Content, text_input};
-use iced::{Element, Task, color};
-use tracing::error;
+use iced::keyboard;
+use iced::widget::{column, scrollable, text, text_editor, text_input};
+use iced::{Element, Task};
+use serde::Serialize;
+use rand::SeedableRng;
+use tracing::{error, warn};
 
-use crate::command::Command;
+use wayline::command::{self, Command};
+use wayline::{api, table};
+
+use crate::event::{EventLog, WaylineEvent};
+use crate::history::{Action, History};
+use crate::scrollback::{LineKind, ScrollbackLine};
+use crate::transcript::Transcript;
 
 const MAIN_INPUT_ID: &str = "wayline-main-textinput";
+const SCROLLBACK_ID: &str = "wayline-scrollback";
+const CONFIG_PATH: &str = "tables.toml";
+const CONFIG_DIR_NAME: &str = "wayline"; // App name for the platform's config directory
+const SESSION_PATH: &str = "session.json";
+const INPUT_HISTORY_PATH: &str = "wayline_history";
+const DEFAULT_PROMPT: &str = "> ";
+const DEFAULT_TITLE: &str = "wayline";
+const DEFAULT_EDITOR: &str = if cfg!(windows) { "notepad" } else { "vi" }; // When $EDITOR is unset
+const LIST_PAGE_SIZE: usize = 20;
+const ODDS_BAR_WIDTH: f64 = 20.0; // Characters in the most likely entry's bar for `odds`
+const MAX_ROLL_LOG: usize = 200; // Lines kept in the roll log panel
+const MAX_RECENT_ROLLS: usize = 20; // Rolls kept for `rolls`
+const DEFAULT_CLOCK: &str = "default";
+const TURN_MINUTES: u32 = 10;
+const DEFAULT_TIME_ANCHORS: [(&str, u32); 4] =
+    [("dawn", 6 * 60), ("noon", 12 * 60), ("dusk", 18 * 60), ("midnight", 0)];
+const TURNS_PER_WATCH: u32 = 24; // four hours
+const DEFAULT_MAX_TABLE_DEPTH: usize = 10; // How far `gen` follows subtables, in case they loop
+const MAX_UNIQUE_REROLLS: u32 = 100; // How long `roll <times> <table> unique` looks for new entries
+const DEFAULT_KEYBINDINGS: [(&str, &str); 4] =
+    [("f1", "help"), ("f5", "reload"), ("ctrl+r", "roll"), ("ctrl+l", "clear")];
 
 #[derive(Debug, Default)]
 pub struct Wayline {
     // UI state
-    scrollback: Vec<String>,
+    scrollback: Vec<ScrollbackLine>,
+    last_result: Option<String>, // Most recent result line, reprinted by `last`
+    roll_log: Vec<String>, // Roll results only, newest last, for the roll log panel
+    roll_log_content: text_editor::Content, // `roll_log` as shown, so it can be selected
+    roll_log_hidden: bool, // Toggled with `log`
+    autoscroll_off: bool, // Toggled with `autoscroll`: new lines don't move the scrollback
     input: String,
-    content: Content,
+    input_history: Vec<String>, // Commands entered, oldest first; saved between runs
+    history_cursor: Option<usize>, // Entry of `input_history` recalled with Up/Down, if any
+    prompt: Option<String>, // Echo prefix; `None` means `DEFAULT_PROMPT`
+    quiet: bool, // From `--quiet` or `[ui]`: no info lines until startup is done
+    started: bool, // Whether the config has loaded and startup commands are running
+    keybindings: HashMap<String, String>, // From `[keybindings]`, over `DEFAULT_KEYBINDINGS`
+    aliases: HashMap<String, String>, // From `[aliases]`, keyed in lowercase
+    startup_commands: Vec<String>, // From the config's `[startup]` section
+    title: Option<String>, // From `[window]`; `None` means `DEFAULT_TITLE`
+    theme: Option<iced::Theme>, // From `[window]`; `None` means `iced::Theme::Ferra`
+
+    // Remaining pages of the last `list`, shown by `more`
+    pending_listing: Vec<String>,
+    listing_page: usize,
 
-    // Table loaded from TOML
+    // Table loaded from TOML, keyed by the name as written
     current_table: Option<String>,
+    default_table: Option<String>, // From the config's top-level `default`
+    max_table_depth: Option<usize>, // From the config; `None` means `DEFAULT_MAX_TABLE_DEPTH`
+    previous_table: Option<String>, // Table selected before the current one, for `back`
     tables: HashMap<String, table::Table>,
+    table_sources: HashMap<String, PathBuf>, // File each table was loaded from, by table key
+    case_sensitive: bool, // Whether table names must match exactly
+    listed_tables: Vec<String>, // Table names in the order the last `list` numbered them
+
+    // In-game time tracking, one clock per party; `None` is the default clock
+    clocks: HashMap<String, u32>, // minutes, by clock name
+    active_clock: Option<String>,
+    turns: u32, // 10-minute dungeon turns advanced with `turn` and `watch`
+    time_anchors: HashMap<String, u32>, // From `[time.anchors]`, over `DEFAULT_TIME_ANCHORS`
+    time_bands: Vec<(u32, String)>, // From `[time.bands]`; empty means `api::DEFAULT_TIME_BANDS`
+
+    // Time and table changes, for `undo` and `redo`
+    history: History,
+
+    // Running sum of `+<dice>` rolls, e.g. damage over a fight
+    total: u32,
+
+    // The latest rolls, newest last, for `rolls`
+    recent_rolls: VecDeque<RecentRoll>,
+
+    // Print output to stdout instead of the scrollback, for one-shot CLI use
+    headless: bool,
+    headless_failed: bool, // Whether an error line has been printed headless
+
+    // Started with `--no-config`: tables only load on an explicit `reload`
+    no_config: bool,
+
+    // From `--tables-dir`: every table file in it is loaded along with the config
+    tables_dir: Option<PathBuf>,
+
+    // The config file in use, from `resolve_config_path`; `None` means `CONFIG_PATH`
+    config_path: Option<PathBuf>,
+
+    // Structured events are written here as well, if `--log` was given
+    event_log: Option<EventLog>,
+
+    // Every scrollback line is appended here too, if `--log-file` was given
+    transcript: Option<Transcript>,
+}
+
+/// What rolling a table gave, before it's shown. `roll --json` prints it as it serializes.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct RollOutcome {
+    table: String,
+    dice: String, // The dice rolled, which a `with` override may have changed
+    roll: u32, // After any reroll and modifiers
+    breakdown: String, // How the roll came about, e.g. "4+3=7 +1 night = 8"
+    entry: Option<String>, // The matching entry's name, with any dice in it rolled
+    result: Option<String>, // `entry`, or else the table's fallback
+    #[serde(skip)]
+    line: String, // The result as shown in the scrollback
+    warnings: Vec<String>, // Problems with the table that didn't stop the roll
+}
+
+/// A roll as remembered for `rolls`.
+#[derive(Debug)]
+struct RecentRoll {
+    source: String, // Table name, or the dice for a plain roll
+    roll: u32,
+    result: Option<String>, // `None` for plain rolls and rolls no entry covered
+    from_table: bool,
+}
+
+/// Command-line arguments: leading flags, then an optional command to run headless.
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    log: Option<String>, // `--log <path>`
+    log_file: Option<String>, // `--log-file <path>`
+    no_config: bool, // `--no-config`
+    quiet: bool, // `--quiet`
+    tables_dir: Option<PathBuf>, // `--tables-dir <path>`
+    config: Option<PathBuf>, // `--config <path>`
+    check: Option<PathBuf>, // `--check <path>`: validate the file's tables and exit
+    command: Vec<String>,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--log" => parsed.log = Some(args.next().ok_or("--log needs a file path")?),
+            "--log-file" => {
+                parsed.log_file = Some(args.next().ok_or("--log-file needs a file path")?)
+            }
+            "--no-config" => parsed.no_config = true,
+            "--quiet" => parsed.quiet = true,
+            "--tables-dir" => {
+                let dir = args.next().ok_or("--tables-dir needs a directory path")?;
+                parsed.tables_dir = Some(PathBuf::from(dir));
+            }
+            "--config" => {
+                let path = args.next().ok_or("--config needs a file path")?;
+                parsed.config = Some(PathBuf::from(path));
+            }
+            "--check" => {
+                let path = args.next().ok_or("--check needs a file path")?;
+                parsed.check = Some(PathBuf::from(path));
+            }
+            _ => {
+                parsed.command.push(arg);
+                parsed.command.extend(args);
+                break;
+            }
+        }
+    }
+    Ok(parsed)
+}
 
-    // In-game time tracking
-    current_time_minutes: u32,
+/// What loading the config found: how many tables loaded, why anything else didn't, and what
+/// looks wrong with the tables that did.
+#[derive(Debug, Default)]
+pub struct LoadSummary {
+    loaded: usize,
+    failures: Vec<String>,
+    warnings: Vec<String>, // e.g. "tables.toml: table 'Monsters': no entry covers 3-6"
 }
 
 #[derive(Debug, Clone)]
@@ -34,21 +206,54 @@ pub enum Message {
     WindowClosed,
     EnterPressed,
     ContentChanged(String),
+    ConfigLoaded(Result<String, String>),
+    ConfigChanged,
+    ConfigReloaded(Result<String, String>),
+    KeyPressed(keyboard::Key, keyboard::Modifiers),
+    EditorClosed(Result<(), String>),
+    RollLogAction(text_editor::Action),
 }
 
 impl Wayline {
-    fn new() -> (Self, Task<Message>) {
-        let w: Wayline = Self::default();
+    fn new(args: Args, event_log: Option<EventLog>) -> (Self, Task<Message>) {
+        let mut w = Wayline {
+            event_log,
+            no_config: args.no_config,
+            quiet: args.quiet,
+            tables_dir: args.tables_dir,
+            config_path: Some(resolve_config_path(args.config, user_config_dir(), Path::exists)),
+            ..Self::default()
+        };
+        if let Some(path) = &args.log_file {
+            w.open_transcript(path);
+        }
         let task = text_input::focus(MAIN_INPUT_ID);
         (w, task)
     }
 
     pub fn subscription(&self) -> iced::Subscription<Message> {
-        iced::window::events().map(|(_, event)| match event {
+        let window_events = iced::window::events().map(|(_, event)| match event {
             iced::window::Event::Opened { .. } => Message::WindowOpened,
             iced::window::Event::Closed => Message::WindowClosed,
             _ => Message::Noop,
-        })
+        });
+        // Listens even to keys the input box has handled, since it's always focused; keys
+        // bound to commands don't type anything into it.
+        let key_presses = iced::event::listen_with(|event, _, _| match event {
+            iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                Some(Message::KeyPressed(key, modifiers))
+            }
+            _ => None,
+        });
+        iced::Subscription::batch([
+            window_events,
+            key_presses,
+            config_watch::subscription(self.config_path()),
+        ])
+    }
+
+    fn config_path(&self) -> &Path {
+        self.config_path.as_deref().unwrap_or(Path::new(CONFIG_PATH))
     }
 
     pub fn table(&self) -> Option<&table::Table> {
@@ -59,43 +264,289 @@ impl Wayline {
         }
     }
 
-    pub fn read_config(&self, path: &str) -> Option<String> {
-        match std::fs::read_to_string(path) {
-            Ok(content) => Some(content),
+    /// The key of the table called `name`. An exact match always wins; otherwise, unless
+    /// matching is case-sensitive, names are compared ignoring case, and of several tables
+    /// differing only by case the first in sorted order is picked.
+    fn resolve_table_name(&self, name: &str) -> Option<&String> {
+        if let Some((key, _)) = self.tables.get_key_value(name) {
+            return Some(key);
+        }
+        if self.case_sensitive {
+            return None;
+        }
+        let name = name.to_lowercase();
+        self.tables.keys().filter(|key| key.to_lowercase() == name).min()
+    }
+
+    fn sorted_table_names(&self) -> Vec<String> {
+        let mut table_names: Vec<String> = self.tables.keys().cloned().collect();
+        table_names.sort();
+        table_names
+    }
+
+    fn find_table(&self, name: &str) -> Option<&table::Table> {
+        self.resolve_table_name(name).map(|key| &self.tables[key])
+    }
+
+    /// The table called `target`, or the current table if there's no target, with the error to
+    /// show if there's no such table.
+    fn resolve_target(&self, target: Option<&str>) -> Result<&table::Table, String> {
+        match target {
+            Some(name) => {
+                self.find_table(name).ok_or_else(|| format!("Table '{}' not found.", name))
+            }
+            None => self.table().ok_or_else(|| "No table selected.".to_string()),
+        }
+    }
+
+    pub fn load_all(&mut self, toml_str: &str) -> LoadSummary {
+        let path = self.config_path().to_path_buf();
+        self.load_all_from(&path, toml_str)
+    }
+
+    /// Loads tables and settings from `toml_str`, read from `path`, along with the tables of
+    /// any files it includes. Settings are only read from the top-level file.
+    pub fn load_all_from(&mut self, path: &Path, toml_str: &str) -> LoadSummary {
+        let mut summary = LoadSummary::default();
+        let (included, errors) = api::read_includes(path, toml_str);
+        for e in errors {
+            error!("{}", e);
+            summary.failures.push(e);
+        }
+        self.load_tables(path, toml_str, &mut summary);
+        for (include_path, source) in included {
+            self.load_tables(&include_path, &source, &mut summary);
+        }
+
+        self.load_settings(toml_str);
+        summary
+    }
+
+    fn load_tables(&mut self, path: &Path, toml_str: &str, summary: &mut LoadSummary) {
+        match api::parse_tables_lenient(toml_str) {
+            Ok((tables, errors)) => {
+                for table in tables {
+                    self.add_table(path, table, summary);
+                }
+                for (index, e) in errors {
+                    error!("Failed to parse table #{} in {}: {}", index + 1, path.display(), e);
+                    summary.failures.push(format!(
+                        "table #{} in {}: {}",
+                        index + 1,
+                        path.display(),
+                        e.message()
+                    ));
+                }
+            }
             Err(e) => {
-                error!("Failed to read config file {}: {}", path, e);
-                None
+                error!("Failed to parse tables in {}: {}", path.display(), e);
+                summary.failures.push(format!("{}: {}", path.display(), e.message()));
             }
         }
     }
 
-    pub fn load_all(&mut self, toml_str: &str) {
-        match api::parse_tables(toml_str) {
+    fn load_json_tables(&mut self, path: &Path, json_str: &str, summary: &mut LoadSummary) {
+        match api::parse_tables_json(json_str) {
             Ok(tables) => {
                 for table in tables {
-                    self.tables.insert(table.name.to_lowercase(), table);
+                    self.add_table(path, table, summary);
+                }
+            }
+            Err(e) => {
+                error!("Failed to parse tables in {}: {}", path.display(), e);
+                summary.failures.push(format!("{}: {}", path.display(), e));
+            }
+        }
+    }
+
+    fn add_table(&mut self, path: &Path, mut table: table::Table, summary: &mut LoadSummary) {
+        let weight_problem = api::assign_weight_ranges(&mut table);
+        for problem in weight_problem.into_iter().chain(api::validate_table(&table)) {
+            warn!("Table {:?}: {}", table.name, problem);
+            let shown = format!("Warning: table '{}': {}.", table.name, problem);
+            if !self.headless {
+                self.update_scrollback(shown);
+            } else if !self.quiet {
+                // On stderr, so a headless command's own output can still be piped.
+                eprintln!("{}", shown);
+            }
+            let warning = format!("{}: table '{}': {}", path.display(), table.name, problem);
+            summary.warnings.push(warning);
+        }
+        self.table_sources.insert(table.name.clone(), path.to_path_buf());
+        self.tables.insert(table.name.clone(), table);
+        summary.loaded += 1;
+    }
+
+    /// Loads every `.toml` and `.json` file directly inside `dir`, returning what each loaded
+    /// in path order.
+    fn load_tables_dir(&mut self, dir: &Path) -> std::io::Result<Vec<(PathBuf, LoadSummary)>> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && table_file_kind(path).is_some())
+            .collect();
+        paths.sort();
+
+        let mut results = Vec::new();
+        for path in paths {
+            let mut summary = LoadSummary::default();
+            match (std::fs::read_to_string(&path), table_file_kind(&path)) {
+                (Ok(source), Some("json")) => self.load_json_tables(&path, &source, &mut summary),
+                (Ok(source), _) => self.load_tables(&path, &source, &mut summary),
+                (Err(e), _) => {
+                    error!("Failed to read {}: {}", path.display(), e);
+                    summary.failures.push(format!("{}: {}", path.display(), e));
+                }
+            }
+            results.push((path, summary));
+        }
+        Ok(results)
+    }
+
+    /// Loads the `--tables-dir` directory, if one was given, and reports each file in it.
+    fn load_and_report_tables_dir(&mut self) {
+        let Some(dir) = self.tables_dir.clone() else {
+            return;
+        };
+        let results = match self.load_tables_dir(&dir) {
+            Ok(results) => results,
+            Err(e) => {
+                error!("Failed to read tables directory {}: {}", dir.display(), e);
+                self.show_error(format!("Can't read tables directory {}.", dir.display()));
+                return;
+            }
+        };
+        if results.is_empty() {
+            self.update_scrollback(format!("No .toml or .json files in {}.", dir.display()));
+        }
+        for (path, summary) in results {
+            if summary.failures.is_empty() {
+                self.update_scrollback(format!(
+                    "Loaded {} tables from {}.",
+                    summary.loaded,
+                    path.display()
+                ));
+                continue;
+            }
+            self.show_error(format!(
+                "Loaded {} tables from {}, {} failed:",
+                summary.loaded,
+                path.display(),
+                summary.failures.len()
+            ));
+            for failure in &summary.failures {
+                self.show_error(format!("  {}", failure));
+            }
+        }
+    }
+
+    fn load_settings(&mut self, toml_str: &str) {
+        match config::parse_config(toml_str) {
+            Ok(config) => {
+                if let Some(prompt) = config.ui.prompt {
+                    self.prompt = Some(prompt);
+                }
+                self.quiet |= config.ui.quiet;
+                if let Some(autoscroll) = config.ui.autoscroll {
+                    self.autoscroll_off = !autoscroll;
+                }
+                self.title = config.window.title;
+                self.theme = config.window.theme.and_then(|name| {
+                    let theme = theme_named(&name);
+                    if theme.is_none() {
+                        self.show_error(format!("Unknown theme '{}', using Ferra.", name));
+                    }
+                    theme
+                });
+                self.default_table = config.default;
+                self.max_table_depth = config.max_table_depth;
+                self.case_sensitive = config.matching.case_sensitive;
+                self.startup_commands = config.startup.commands;
+                self.keybindings = config
+                    .keybindings
+                    .into_iter()
+                    .map(|(combo, command)| (config::normalize_key(&combo), command))
+                    .collect();
+                self.aliases = config
+                    .aliases
+                    .into_iter()
+                    .map(|(alias, command)| (alias.to_lowercase(), command))
+                    .collect();
+                self.time_anchors.clear();
+                for (name, time) in config.time.anchors {
+                    match api::parse_time_of_day(&time) {
+                        Some(minutes) => {
+                            self.time_anchors.insert(name.to_lowercase(), minutes);
+                        }
+                        None => self.show_error(format!(
+                            "Skipped time anchor '{}': '{}' isn't HH:MM.",
+                            name, time
+                        )),
+                    }
+                }
+                self.time_bands.clear();
+                for (name, start) in config.time.bands {
+                    match api::parse_time_of_day(&start) {
+                        Some(minutes) => self.time_bands.push((minutes, name)),
+                        None => self.show_error(format!(
+                            "Skipped time band '{}': '{}' isn't HH:MM.",
+                            name, start
+                        )),
+                    }
                 }
             }
             Err(e) => {
-                error!("Failed to parse tables: {}", e);
+                error!("Failed to parse config: {}", e);
             }
         }
     }
 
+    fn title(&self) -> String {
+        self.title.clone().unwrap_or_else(|| DEFAULT_TITLE.to_string())
+    }
+
+    fn theme(&self) -> iced::Theme {
+        self.theme.clone().unwrap_or(iced::Theme::Ferra)
+    }
+
+    fn prompt(&self) -> &str {
+        self.prompt.as_deref().unwrap_or(DEFAULT_PROMPT)
+    }
+
     pub fn view(&self) -> Element<'_, Message> {
-        // Editor and input take up the full width and height of the window.
-        // Output from the Wayline system will be displayed in the editor (scrollback) area.
-        column![
-            // Scrollback
-            text_editor(&self.content)
-                .padding(10)
+        // Scrollback and input take up the full width and height of the window.
+        // Output from the Wayline system will be displayed in the scrollback area, one styled
+        // text per line so results and errors stand out.
+        let lines = self.scrollback.iter().map(|line| {
+            let kind = line.kind;
+            text(line.text.as_str())
                 .size(14)
-                .style(|theme, status| {
-                    let mut style = iced::widget::text_editor::default(theme, status);
-                    style.value = color!(0xEEEEEE);
-                    style
-                })
-                .height(iced::Length::FillPortion(9)),
+                .style(move |theme| kind.style(theme))
+                .into()
+        });
+        // Roll results alone, below the scrollback, so the last roll is easy to find.
+        let roll_log = (!self.roll_log_hidden).then(|| {
+            text_editor(&self.roll_log_content)
+                .on_action(Message::RollLogAction)
+                .size(14)
+                .height(iced::Length::FillPortion(3))
+        });
+        // Anchored to the bottom, the scrollback stays on the newest line as lines come in.
+        let mut scrollback = scrollable(column(lines).padding(10).width(iced::Length::Fill))
+            .id(scrollable::Id::new(SCROLLBACK_ID))
+            .height(iced::Length::FillPortion(9));
+        if !self.autoscroll_off {
+            scrollback = scrollback.anchor_bottom();
+        }
+        column![
+            // Status line
+            text(self.status_line())
+                .size(12)
+                .style(text::secondary),
+            scrollback,
+        ]
+        .push_maybe(roll_log)
+        .push(
             // Input area
             text_input("enter command", &self.input)
                 .id(MAIN_INPUT_ID)
@@ -103,210 +554,4354 @@ impl Wayline {
                 .size(14)
                 .on_input(Message::ContentChanged)
                 .on_submit(Message::EnterPressed),
-        ]
+        )
         .spacing(10)
         .into()
     }
 
-    pub fn update(&mut self, message: Message) {
+    pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::EnterPressed => {
-                self.on_enter_pressed();
+                return self.on_enter_pressed();
             }
             Message::ContentChanged(new_input) => {
                 self.input = new_input;
+                self.history_cursor = None;
             }
             Message::WindowOpened => {
                 self.update_scrollback("Wayline window opened.");
-
-                if let Some(config) = self.read_config("tables.toml") {
-                    self.load_all(&config);
-                    self.update_scrollback(format!(
-                        "Loaded tables from tables.toml: {:?}.",
-                        self.tables.keys()
-                    ));
-                    if let Some(first_table_name) = self.tables.keys().next() {
-                        self.current_table = Some(first_table_name.clone());
-                        self.update_scrollback(format!(
-                            "Current table set to '{}'.",
-                            first_table_name
-                        ));
+                match input_history::load(INPUT_HISTORY_PATH) {
+                    Ok(history) => self.input_history = history,
+                    Err(e) => error!("{}", e),
+                }
+                if self.no_config {
+                    if self.tables_dir.is_some() {
+                        self.load_and_report_tables_dir();
+                        self.select_default_table();
+                    } else {
+                        self.update_scrollback("Started with no tables loaded.");
                     }
-                } else {
-                    self.update_scrollback("No tables.toml found.");
+                    self.started = true;
+                    return Task::none();
+                }
+                self.update_scrollback(format!(
+                    "Reading config from {}.",
+                    self.config_path().display()
+                ));
+                return load_config(self.config_path());
+            }
+            Message::ConfigLoaded(Ok(config)) => {
+                let summary = self.load_all(&config);
+                self.report_load("Loaded", &summary);
+                self.load_and_report_tables_dir();
+                self.restore_session();
+                if self.current_table.is_none() {
+                    self.select_default_table();
+                }
+                self.started = true;
+                return self.run_startup_commands();
+            }
+            Message::ConfigLoaded(Err(e)) => {
+                error!("{}", e);
+                self.show_error(format!("No {} found.", self.config_path().display()));
+                if self.tables_dir.is_some() {
+                    self.load_and_report_tables_dir();
+                    self.select_default_table();
+                }
+                self.started = true;
+            }
+            Message::ConfigChanged if self.no_config => {}
+            Message::ConfigChanged => {
+                return reload_config(self.config_path());
+            }
+            Message::ConfigReloaded(Ok(config)) => {
+                self.reload_tables(&config);
+            }
+            Message::ConfigReloaded(Err(e)) => {
+                error!("{}", e);
+                self.show_error(format!("Failed to reload {}.", self.config_path().display()));
+            }
+            Message::KeyPressed(key, modifiers) => {
+                return self.on_key_pressed(&key, modifiers);
+            }
+            // Read-only: the log can be selected and copied, but not edited.
+            Message::RollLogAction(action) if action.is_edit() => {}
+            Message::RollLogAction(action) => self.roll_log_content.perform(action),
+            Message::EditorClosed(Ok(())) => {
+                return reload_config(self.config_path());
+            }
+            Message::EditorClosed(Err(e)) => {
+                error!("{}", e);
+                self.show_error(e);
+            }
+            Message::WindowClosed => {
+                if let Err(e) = session::save(SESSION_PATH, &self.session_state()) {
+                    error!("{}", e);
+                }
+                if let Err(e) = input_history::save(INPUT_HISTORY_PATH, &self.input_history) {
+                    error!("{}", e);
                 }
             }
             _ => { /* Ignore other messages */ }
         }
+
+        Task::none()
+    }
+
+    /// Replaces the loaded tables with those in `toml_str`, keeping the current table selected
+    /// if it's still there.
+    fn reload_tables(&mut self, toml_str: &str) {
+        self.tables.clear();
+        self.table_sources.clear();
+        let summary = self.load_all(toml_str);
+        self.report_load("Reloaded", &summary);
+        self.load_and_report_tables_dir();
+        if let Some(table_name) = self.current_table.take() {
+            if let Some(key) = self.resolve_table_name(&table_name) {
+                self.current_table = Some(key.clone());
+            } else {
+                self.update_scrollback(format!("Table '{}' no longer exists.", table_name));
+            }
+        }
+    }
+
+    /// Lists the loaded tables, or if some failed to load, how many did and why the rest didn't.
+    fn report_load(&mut self, verb: &str, summary: &LoadSummary) {
+        if summary.failures.is_empty() {
+            self.update_scrollback(format!(
+                "{} tables from {}: {:?}.",
+                verb,
+                self.config_path().display(),
+                self.sorted_table_names()
+            ));
+            return;
+        }
+        self.show_error(format!(
+            "{} {} tables from {}, {} failed:",
+            verb,
+            summary.loaded,
+            self.config_path().display(),
+            summary.failures.len()
+        ));
+        for failure in &summary.failures {
+            self.show_error(format!("  {}", failure));
+        }
+    }
+
+    /// Selects the config's `default` table, or if there isn't one, the alphabetically first.
+    fn select_default_table(&mut self) {
+        let configured = self.default_table.clone().and_then(|name| {
+            let key = self.resolve_table_name(&name).cloned();
+            if key.is_none() {
+                self.show_error(format!("Default table '{}' not found.", name));
+            }
+            key
+        });
+        if let Some(key) = configured.or_else(|| self.tables.keys().min().cloned()) {
+            self.update_scrollback(format!("Current table set to '{}'.", key));
+            self.current_table = Some(key);
+        }
+    }
+
+    fn session_state(&self) -> session::SessionState {
+        session::SessionState {
+            current_table: self.current_table.clone(),
+            current_time_minutes: self.clocks.get(DEFAULT_CLOCK).copied().unwrap_or_default(),
+        }
+    }
+
+    /// Restores time and the current table from the last session, if one was saved.
+    /// The table is only restored if it is still among the loaded tables.
+    fn restore_session(&mut self) {
+        let state = match session::load(SESSION_PATH) {
+            Ok(state) => state,
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        };
+
+        self.clocks.insert(DEFAULT_CLOCK.to_string(), state.current_time_minutes);
+        if let Some(table_name) = state.current_table
+            && let Some(key) = self.resolve_table_name(&table_name).cloned()
+        {
+            self.update_scrollback(format!("Restored table '{}' from last session.", key));
+            self.current_table = Some(key);
+        }
     }
 
     /// If no table is loaded, do nothing.
-    /// If multiple tables are loaded but none is selected, list table names.
+    /// If multiple tables are loaded but none is selected, list table names, numbered for `use`.
     /// If one table is selected, list its entries.
     /// If the user passed `all`, list all tables whether or not any table is selected.
     fn on_list_command(&mut self, it: Option<String>) {
         if self.tables.is_empty() {
-            self.update_scrollback("No tables loaded.");
+            self.show_error("No tables loaded.");
             return;
         }
 
         let maybe_table = if let Some(ref table_name) = it {
-            if table_name == "all" {
+            if table_name.eq_ignore_ascii_case("all") {
                 None
             } else {
-                self.tables.get(table_name)
+                self.find_table(table_name)
             }
         } else {
             self.table()
         };
 
         if let Some(target) = maybe_table {
-            self.update_scrollbacks(get_table_info(target));
+            let lines = get_table_info(target);
+            self.show_paged(lines);
         } else {
             self.update_scrollback("Loaded tables:");
-            let mut lines: Vec<String> = self
-                .tables
-                .keys()
-                .map(|name| format!("- {}", name))
+            let table_names = self.sorted_table_names();
+            let lines = table_names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| {
+                    let table = &self.tables[name];
+                    let entries = match table.rows.len() {
+                        1 => "1 entry".to_string(),
+                        count => format!("{} entries", count),
+                    };
+                    format!("{}. {} ({}, {})", index + 1, name, table.dice, entries)
+                })
                 .collect();
-            lines.sort();
-            self.update_scrollbacks(lines);
+            self.listed_tables = table_names;
+            self.show_paged(lines);
         }
     }
 
-    fn on_time_command(&mut self) {
-        let hours = self.current_time_minutes / 60;
-        let minutes = self.current_time_minutes % 60;
-        self.update_scrollback(format!("Current in-game time: {:02}:{:02}", hours, minutes));
-    }
-
-    fn add_minutes(&mut self, minutes: u32) {
-        self.current_time_minutes += minutes;
-        self.update_scrollback(format!(
-            "Added {} minutes. New time: {:02}:{:02}",
-            minutes,
-            self.current_time_minutes / 60,
-            self.current_time_minutes % 60
-        ));
+    /// Lists each loaded table with the file it came from.
+    fn on_tables_command(&mut self) {
+        if self.tables.is_empty() {
+            self.show_error("No tables loaded.");
+            return;
+        }
+        let lines: Vec<String> = self
+            .sorted_table_names()
+            .into_iter()
+            .map(|name| match self.table_sources.get(&name) {
+                Some(path) => format!("{}: {}", name, path.display()),
+                None => name,
+            })
+            .collect();
+        self.show_paged(lines);
     }
 
-    fn on_enter_pressed(&mut self) {
-        self.update_scrollback(format!("> {}", self.input));
-
-        let cmd = command::parse_command(&self.input);
+    /// Lists the tables rolled on `dice`, however it's written, or with no dice, every table
+    /// grouped by its dice.
+    fn on_find_table_command(&mut self, dice: Option<String>) {
+        let mut by_dice: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for name in self.sorted_table_names() {
+            let key = api::normalize_dice(&self.tables[&name].dice);
+            by_dice.entry(key).or_default().push(name);
+        }
 
-        match cmd {
-            Command::RollTable(table_name_opt) => self.on_roll_command(table_name_opt),
-            Command::RollDice(dice_str) => {
-                if let Some(roll) = api::roll(&dice_str) {
-                    self.update_scrollback(format!("Rolled {}: {}", dice_str, roll));
-                } else {
-                    self.update_scrollback(format!("Invalid dice notation: {}", dice_str));
-                }
-            }
-            Command::List(it) => self.on_list_command(it),
-            Command::Time => self.on_time_command(),
-            Command::Add(minutes) => self.add_minutes(minutes),
-            Command::Help => {
-                self.update_scrollback("Available commands:");
-                self.update_scrollback("- use <table name> : Select a table as current");
-                self.update_scrollback(
-                    "- roll [table name] : Roll on the current table or a table with [table name]",
-                );
-                self.update_scrollback("- dice <notation> : Roll custom dice (e.g., '2d6')");
-                self.update_scrollback("- list [table name] : List the current table entries, or all tables if current table is unset");
-                self.update_scrollback("- time : Show current in-game time");
-                self.update_scrollback("- add <minutes> : Add minutes to in-game time");
-                self.update_scrollback("- help : Show this help message");
-            }
-            Command::Unknown(cmd) => {
-                self.update_scrollback(format!("Unknown command: {}", cmd));
-            }
-            Command::Use(table_name) => {
-                if self.tables.contains_key(&table_name) {
-                    self.current_table = Some(table_name.clone());
-                    self.update_scrollback(format!("Switched to table '{}'.", table_name));
-                } else {
-                    self.update_scrollback(format!("Table '{}' not found.", table_name));
+        match dice {
+            Some(dice) => match by_dice.get(&api::normalize_dice(&dice)) {
+                Some(names) => {
+                    self.show_result(format!("Tables rolled on {}: {}.", dice, names.join(", ")))
                 }
+                None => self.update_scrollback(format!("No tables are rolled on {}.", dice)),
+            },
+            None if by_dice.is_empty() => self.show_error("No tables loaded."),
+            None => {
+                let lines: Vec<String> = by_dice
+                    .into_iter()
+                    .map(|(dice, names)| format!("{}: {}", dice, names.join(", ")))
+                    .collect();
+                self.show_paged(lines);
             }
         }
-
-        self.input.clear();
     }
 
-    fn on_roll_command(&mut self, target: Option<String>) {
-        let maybe_table = match target {
-            Some(ref name) => self.tables.get(name),
-            None => self.table(),
-        };
-
-        let Some(table) = maybe_table else {
-            if let Some(ref name) = target {
-                self.update_scrollback(format!("Table '{}' not found.", name));
-            } else {
-                self.update_scrollback("No table selected.");
+    /// Lists a table's entries from most to least likely, each with its chance and a bar
+    /// scaled so the most likely entry's is `ODDS_BAR_WIDTH` long.
+    fn on_odds_command(&mut self, target: Option<String>) {
+        let table = match self.resolve_target(target.as_deref()) {
+            Ok(table) => table,
+            Err(e) => {
+                self.show_error(e);
+                return;
             }
+        };
+        let Some(probabilities) = api::entry_probabilities(table) else {
+            let error = format!("Can't work out the odds of {} on {}.", table.name, table.dice);
+            self.show_error(error);
             return;
         };
 
-        let table_name = table.name.to_lowercase();
-        let dice = table.dice.clone();
+        let mut odds: Vec<(&table::Entry, f64)> = table.rows.iter().zip(probabilities).collect();
+        odds.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        let most = odds.first().map_or(0.0, |(_, p)| *p);
+        let labels: Vec<String> = odds
+            .iter()
+            .map(|(entry, _)| {
+                let numbers = if api::is_weighted(&table.dice) {
+                    format!("[weight {}]", entry.weight.unwrap_or(1))
+                } else {
+                    format_numbers(entry)
+                };
+                format!("{} {}", entry.name, numbers)
+            })
+            .collect();
+        let width = labels.iter().map(|label| label.chars().count()).max().unwrap_or(0);
 
-        let (roll, result) = api::roll_on(table, &dice);
+        let mut lines = vec![format!(
+            "Odds on {} ({}), most likely first:",
+            table.name, table.dice
+        )];
+        for ((entry, p), label) in odds.iter().zip(&labels) {
+            // An entry that never comes up has no bar, so a note takes its place.
+            let bar = match *p {
+                0.0 if !entry.enabled => "(disabled)".to_string(),
+                0.0 => "(never comes up)".to_string(),
+                p => "#".repeat((p / most * ODDS_BAR_WIDTH).round() as usize),
+            };
+            lines.push(format!("  {:<width$} {:>5.1}% {}", label, p * 100.0, bar));
+        }
+        self.show_results(lines);
+    }
 
-        match result {
-            Some(entry) => {
-                self.update_scrollback(format!(
-                    "{} -> ({}): rolled: {}",
-                    table_name, roll, entry.name
-                ));
+    /// Shows how adding `modifier` to a table's rolls would change which of its entries can come
+    /// up, by shifting the range its dice roll.
+    fn on_what_if_command(&mut self, target: Option<String>, modifier: i64) {
+        let table = match self.resolve_target(target.as_deref()) {
+            Ok(table) => table,
+            Err(e) => {
+                self.show_error(e);
+                return;
             }
-            None => {
-                self.update_scrollback(format!(
-                    "{} -> ({}): no matching entry found.",
-                    table_name, roll
+        };
+        let range = match api::dice_range(&table.dice) {
+            Some(range) if api::digit_dice(&table.dice).is_none() => range,
+            _ => {
+                self.show_error(format!(
+                    "Can't shift {}'s dice, {}, by a modifier.",
+                    table.name, table.dice
                 ));
+                return;
             }
+        };
+        // Totals stop at 0, as they do when `roll` applies a modifier.
+        let shift = |n: u32| (n as i64 + modifier).clamp(0, u32::MAX as i64) as u32;
+        let shifted = shift(*range.start())..=shift(*range.end());
+
+        let mut lines = vec![format!(
+            "{} ({}) at {:+}: rolls {}-{} instead of {}-{}.",
+            table.name,
+            table.dice,
+            modifier,
+            shifted.start(),
+            shifted.end(),
+            range.start(),
+            range.end()
+        )];
+        let entries = |reachable: bool| -> Vec<String> {
+            table
+                .rows
+                .iter()
+                .filter(|entry| entry.enabled)
+                .filter(|entry| {
+                    let before = api::reachable_in(entry, &range);
+                    let after = api::reachable_in(entry, &shifted);
+                    before != after && after == reachable
+                })
+                .map(|entry| format!("{} {}", entry.name, format_numbers(entry)))
+                .collect()
+        };
+        let (gained, lost) = (entries(true), entries(false));
+        if !gained.is_empty() {
+            lines.push(format!("  Now reachable: {}", gained.join(", ")));
+        }
+        if !lost.is_empty() {
+            lines.push(format!("  No longer reachable: {}", lost.join(", ")));
+        }
+        if gained.is_empty() && lost.is_empty() {
+            lines.push("  The same entries stay reachable.".to_string());
+        }
+        // Results the dice couldn't roll before may have no entry. Only the at most
+        // `MAX_WHATIF_MODIFIER` results past the old range need looking at.
+        let newly_rolled = if modifier > 0 {
+            range.end().saturating_add(1).max(*shifted.start())..=*shifted.end()
+        } else {
+            *shifted.start()..=range.start().saturating_sub(1).min(*shifted.end())
+        };
+        let uncovered: Vec<u32> = newly_rolled
+            .filter(|total| !range.contains(total) && api::find_entry(table, *total).is_none())
+            .collect();
+        if !uncovered.is_empty() {
+            let fallback = match &table.fallback {
+                Some(fallback) => format!(", giving '{}'", fallback),
+                None => String::new(),
+            };
+            let uncovered = api::format_runs(uncovered);
+            lines.push(format!("  No entry covers {}{}.", uncovered, fallback));
         }
+        self.show_results(lines);
     }
 
-    fn update_scrollback<S: Into<String>>(&mut self, new_line: S) {
-        self.scrollback.push(new_line.into());
-        let new_content = self.scrollback.join("\n");
-        self.content = Content::with_text(&new_content);
+    /// Shows each entry's chance of coming up on two tables side by side, matching entries by
+    /// name. Each table uses its own dice.
+    fn on_compare_command(&mut self, left: &str, right: &str) {
+        let mut columns = Vec::new();
+        for name in [left, right] {
+            let Some(table) = self.find_table(name) else {
+                self.show_error(format!("Table '{}' not found.", name));
+                return;
+            };
+            let Some(probabilities) = api::entry_probabilities(table) else {
+                let error = format!("Can't work out the odds of {} on {}.", table.name, table.dice);
+                self.show_error(error);
+                return;
+            };
+            // Entries sharing a name are one result, so their chances add up.
+            let mut by_name: Vec<(String, f64)> = Vec::new();
+            for (entry, p) in table.rows.iter().zip(probabilities) {
+                match by_name.iter_mut().find(|(name, _)| name.eq_ignore_ascii_case(&entry.name)) {
+                    Some((_, total)) => *total += p,
+                    None => by_name.push((entry.name.clone(), p)),
+                }
+            }
+            columns.push((format!("{} ({})", table.name, table.dice), by_name));
+        }
+
+        // Rows follow the first table's order, then entries only the second has.
+        let mut names: Vec<&str> = Vec::new();
+        for (_, by_name) in &columns {
+            for (name, _) in by_name {
+                if !names.iter().any(|known| known.eq_ignore_ascii_case(name)) {
+                    names.push(name);
+                }
+            }
+        }
+        let chance = |by_name: &[(String, f64)], name: &str| {
+            by_name
+                .iter()
+                .find(|(entry, _)| entry.eq_ignore_ascii_case(name))
+                .map(|(_, p)| format!("{:.1}%", p * 100.0))
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        let width = names.iter().map(|name| name.chars().count()).max().unwrap_or(0).max(5);
+        let (left_header, left_chances) = &columns[0];
+        let (right_header, right_chances) = &columns[1];
+        let left_width = left_header.chars().count();
+        let mut lines = vec![format!(
+            "{:<width$}  {}  {}",
+            "Entry",
+            left_header,
+            right_header,
+            width = width
+        )];
+        for name in names {
+            lines.push(format!(
+                "{:<width$}  {:>left_width$}  {:>right_width$}",
+                name,
+                chance(left_chances, name),
+                chance(right_chances, name),
+                width = width,
+                left_width = left_width,
+                right_width = right_header.chars().count()
+            ));
+        }
+        self.show_results(lines);
     }
 
-    fn update_scrollbacks<I, S>(&mut self, new_lines: I)
-    where
-        I: IntoIterator<Item = S>,
-        S: Into<String>,
-    {
-        for line in new_lines {
-            self.update_scrollback(line);
+    fn on_recent_rolls_command(&mut self) {
+        if self.recent_rolls.is_empty() {
+            self.update_scrollback("No rolls yet.");
+            return;
         }
+        let lines: Vec<String> = self
+            .recent_rolls
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(index, recent)| match (&recent.result, recent.from_table) {
+                (Some(result), _) => {
+                    format!("{}. {} ({}): {}", index + 1, recent.source, recent.roll, result)
+                }
+                (None, true) => format!(
+                    "{}. {} ({}): no matching entry",
+                    index + 1,
+                    recent.source,
+                    recent.roll
+                ),
+                (None, false) => format!("{}. {}: {}", index + 1, recent.source, recent.roll),
+            })
+            .collect();
+        self.show_results(lines);
     }
-}
 
-fn get_table_info(table: &table::Table) -> Vec<String> {
-    let mut lines: Vec<String> = vec![
-        format!("Table: {}", table.name),
-        format!("Dice: {}", table.dice),
-    ];
-    for entry in &table.rows {
-        lines.push(format!("- {}: {:?}", entry.name, entry.numbers));
+    fn on_more_command(&mut self) {
+        if self.pending_listing.is_empty() {
+            self.update_scrollback("Nothing more to list.");
+        } else {
+            self.show_listing_page();
+        }
     }
-    lines
-}
 
-pub fn main() {
-    iced::application("wayline", Wayline::update, Wayline::view)
-        .theme(theme)
-        .subscription(Wayline::subscription)
-        .run_with(Wayline::new)
-        .expect("unable to run application")
-}
+    /// Replaces any pending listing with `lines` and shows its first page.
+    /// Headless output isn't interactive, so there it's printed in full instead.
+    fn show_paged(&mut self, lines: Vec<String>) {
+        if self.headless {
+            self.update_scrollbacks(lines);
+            return;
+        }
+        self.pending_listing = lines;
+        self.listing_page = 0;
+        self.show_listing_page();
+    }
+
+    fn show_listing_page(&mut self) {
+        let pages = self.pending_listing.len().div_ceil(LIST_PAGE_SIZE);
+        let page: Vec<String> = self
+            .pending_listing
+            .iter()
+            .skip(self.listing_page * LIST_PAGE_SIZE)
+            .take(LIST_PAGE_SIZE)
+            .cloned()
+            .collect();
+        self.update_scrollbacks(page);
+
+        self.listing_page += 1;
+        if self.listing_page < pages {
+            self.update_scrollback(format!(
+                "page {}/{} — type 'more' to continue",
+                self.listing_page, pages
+            ));
+        } else {
+            if pages > 1 {
+                self.update_scrollback(format!("page {}/{}", pages, pages));
+            }
+            self.pending_listing.clear();
+        }
+    }
 
-fn theme(_state: &Wayline) -> iced::Theme {
-    iced::Theme::Ferra
+    /// Finds entries named `name` (case-insensitively) and prints the rolls that produce them,
+    /// starting with the current table and then every other table that has a match.
+    fn on_lookup_command(&mut self, name: &str) {
+        if self.tables.is_empty() {
+            self.show_error("No tables loaded.");
+            return;
+        }
+
+        let mut table_names: Vec<&String> = self.tables.keys().collect();
+        table_names.sort();
+        if let Some(current) = &self.current_table {
+            table_names.sort_by_key(|table_name| *table_name != current);
+        }
+
+        let mut lines = Vec::new();
+        for table_name in table_names {
+            let table = &self.tables[table_name];
+            for entry in &table.rows {
+                if entry.name.to_lowercase() == name {
+                    lines.push(format!(
+                        "{}: {} -> {}",
+                        table_name,
+                        entry.name,
+                        format_numbers(entry)
+                    ));
+                }
+            }
+        }
+
+        if lines.is_empty() {
+            self.show_error(format!("No entry named '{}' found.", name));
+        } else {
+            self.show_results(lines);
+        }
+    }
+
+    /// Prints the expected severity of a roll on the named or current table, where rows count
+    /// as severities 1..n in order.
+    fn on_ev_command(&mut self, target: Option<String>) {
+        let table = match self.resolve_target(target.as_deref()) {
+            Ok(table) => table,
+            Err(e) => {
+                self.show_error(e);
+                return;
+            }
+        };
+
+        let table_name = table.name.clone();
+        let dice = table.dice.clone();
+        let rows = table.rows.len();
+        let Some((severity, coverage)) = api::expected_severity(table, &dice) else {
+            self.show_error(format!(
+                "Can't compute the expected severity of {} on {}.",
+                table_name, dice
+            ));
+            return;
+        };
+
+        self.show_result(format!(
+            "{}: expected severity {:.2} of {} on {}",
+            table_name, severity, rows, dice
+        ));
+        if coverage < 1.0 - 1e-9 {
+            self.update_scrollback(format!(
+                "Warning: rows cover only {:.1}% of {} outcomes; the rest are ignored.",
+                coverage * 100.0,
+                dice
+            ));
+        }
+    }
+
+    /// Describes in words how the results of the named or current table's dice are spread.
+    /// The target can also be dice notation, like `roll`.
+    fn on_describe_command(&mut self, target: Option<String>) {
+        let dice = match target {
+            Some(name) => match self.find_table(&name) {
+                Some(table) => table.dice.clone(),
+                None if api::is_dice_notation(&name) => name.to_lowercase(),
+                None => {
+                    self.show_error(format!("Table '{}' not found.", name));
+                    return;
+                }
+            },
+            None => match self.table() {
+                Some(table) => table.dice.clone(),
+                None => {
+                    self.show_error("No table selected.");
+                    return;
+                }
+            },
+        };
+
+        match api::describe_dice(&dice) {
+            Some(description) => self.show_result(description),
+            None => self.show_error(format!("Can't describe the results of {}.", dice)),
+        }
+    }
+
+    /// Rolls a check such as `1d6<=2` and reports whether it succeeded.
+    /// With `then_roll`, a successful check goes on to roll the current table.
+    fn on_check_command(&mut self, check: &str, then_roll: bool) {
+        let Some((dice, comparison, threshold)) = api::parse_check(check) else {
+            self.show_error(format!("Invalid check: {}", check));
+            return;
+        };
+        let Some(roll) = api::roll(dice) else {
+            self.show_error(format!(
+                "Too many dice: {} (at most {} per roll)",
+                dice,
+                api::MAX_DICE
+            ));
+            return;
+        };
+
+        let success = comparison.holds(roll, threshold);
+        self.emit(WaylineEvent::CheckMade {
+            check: check.to_string(),
+            roll,
+            success,
+        });
+        if success {
+            self.show_roll(format!("Check {}: rolled {}, success.", check, roll));
+            if then_roll {
+                self.on_roll_command(None, None, Vec::new());
+            }
+        } else {
+            self.show_roll(format!("Check {}: rolled {}, failed.", check, roll));
+        }
+    }
+
+    fn on_help_command(&mut self, topic: Option<String>) {
+        let Some(topic) = topic else {
+            self.update_scrollbacks(help::overview());
+            return;
+        };
+
+        if let Some(entry) = help::find(&topic) {
+            self.update_scrollbacks(help::detail(entry));
+        } else if let Some(suggestion) = help::closest(&topic) {
+            self.update_scrollback(format!(
+                "No help for '{}'. Did you mean '{}'?",
+                topic, suggestion
+            ));
+        } else {
+            self.update_scrollback(format!("No help for '{}'.", topic));
+            self.update_scrollbacks(help::overview());
+        }
+    }
+
+    fn active_clock_name(&self) -> &str {
+        self.active_clock.as_deref().unwrap_or(DEFAULT_CLOCK)
+    }
+
+    /// Minutes on the active clock.
+    fn current_time_minutes(&self) -> u32 {
+        self.clocks
+            .get(self.active_clock_name())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn set_current_time_minutes(&mut self, minutes: u32) {
+        self.set_time_and_turns(minutes, 0);
+    }
+
+    /// Sets the active clock and counts `turns` more dungeon turns, as one change to undo.
+    fn set_time_and_turns(&mut self, minutes: u32, turns: u32) {
+        let clock = self.active_clock_name().to_string();
+        let before = self.current_time_minutes();
+        if before != minutes || turns > 0 {
+            self.history.record(Action::TimeDelta {
+                clock: clock.clone(),
+                before,
+                after: minutes,
+                turns,
+            });
+        }
+        self.turns += turns;
+        self.set_clock_minutes(clock, minutes);
+    }
+
+    fn set_clock_minutes(&mut self, clock: String, minutes: u32) {
+        let previous = self.clocks.get(&clock).copied().unwrap_or_default();
+        self.clocks.insert(clock.clone(), minutes);
+        self.emit(WaylineEvent::TimeAdvanced {
+            clock,
+            minutes: minutes as i64 - previous as i64,
+            time: minutes,
+        });
+    }
+
+    fn on_undo_command(&mut self) {
+        match self.history.undo() {
+            Some(Action::TimeDelta { clock, before, turns, .. }) => {
+                self.update_scrollback(format!(
+                    "Undid time change: clock '{}' back to {}",
+                    clock,
+                    self.format_time(before)
+                ));
+                self.turns = self.turns.saturating_sub(turns);
+                self.set_clock_minutes(clock, before);
+            }
+            Some(Action::TableSwitch { before, after }) => {
+                if self.switch_table_quietly(before.clone(), Some(after)) {
+                    match before {
+                        Some(table) => self
+                            .update_scrollback(format!("Undid table switch: back to '{}'.", table)),
+                        None => self.update_scrollback("Undid table switch: no table selected."),
+                    }
+                }
+            }
+            None => self.update_scrollback("Nothing to undo."),
+        }
+    }
+
+    fn on_redo_command(&mut self) {
+        match self.history.redo() {
+            Some(Action::TimeDelta { clock, after, turns, .. }) => {
+                self.update_scrollback(format!(
+                    "Redid time change: clock '{}' at {}",
+                    clock,
+                    self.format_time(after)
+                ));
+                self.turns += turns;
+                self.set_clock_minutes(clock, after);
+            }
+            Some(Action::TableSwitch { before, after }) => {
+                if self.switch_table_quietly(Some(after.clone()), before) {
+                    self.update_scrollback(format!("Redid table switch: '{}'.", after));
+                }
+            }
+            None => self.update_scrollback("Nothing to redo."),
+        }
+    }
+
+    /// Sets the current and previous tables without recording history, for `undo` and `redo`.
+    /// Leaves them as they are, and says so, if `current` has since been removed.
+    fn switch_table_quietly(&mut self, current: Option<String>, previous: Option<String>) -> bool {
+        if let Some(table) = &current {
+            if !self.tables.contains_key(table) {
+                self.show_error(format!(
+                    "Table '{}' no longer exists, so the current table is unchanged.",
+                    table
+                ));
+                return false;
+            }
+            self.emit(WaylineEvent::TableSwitched { table: table.clone() });
+        }
+        self.current_table = current;
+        self.previous_table = previous;
+        true
+    }
+
+    /// Formats a clock reading's time of day as "HH:MM (label)", labelled by the configured
+    /// time bands.
+    fn format_time(&self, minutes: u32) -> String {
+        let since_midnight = minutes % api::MINUTES_PER_DAY;
+        let clock = format!("{:02}:{:02}", since_midnight / 60, minutes % 60);
+        let label = if self.time_bands.is_empty() {
+            api::time_band(api::DEFAULT_TIME_BANDS, minutes)
+        } else {
+            api::time_band(
+                self.time_bands
+                    .iter()
+                    .map(|(start, label)| (*start, label.as_str())),
+                minutes,
+            )
+        };
+        match label {
+            Some(label) => format!("{} ({})", clock, label),
+            None => clock,
+        }
+    }
+
+    /// The active clock's day, counting from 1, and the minutes since midnight on it.
+    fn day_and_minutes(&self) -> (u32, u32) {
+        let minutes = self.current_time_minutes();
+        (minutes / api::MINUTES_PER_DAY + 1, minutes % api::MINUTES_PER_DAY)
+    }
+
+    /// The always-visible summary above the scrollback: day, time, clock and current table.
+    fn status_line(&self) -> String {
+        let (day, minutes) = self.day_and_minutes();
+        let mut status = format!("Day {}, {:02}:{:02}", day, minutes / 60, minutes % 60);
+        if let Some(clock) = &self.active_clock {
+            status.push_str(&format!(" ({})", clock));
+        }
+        match &self.current_table {
+            Some(table) => status.push_str(&format!(" | {}", table)),
+            None => status.push_str(" | no table"),
+        }
+        status
+    }
+
+    fn on_time_command(&mut self) {
+        let (day, minutes) = self.day_and_minutes();
+        let time = format!("day {}, {}", day, self.format_time(minutes));
+        if self.active_clock.is_some() {
+            self.update_scrollback(format!(
+                "Current in-game time ({}): {}",
+                self.active_clock_name(),
+                time
+            ));
+        } else {
+            self.update_scrollback(format!("Current in-game time: {}", time));
+        }
+        self.update_scrollback(format!(
+            "Turns: {}, watches: {}",
+            self.turns,
+            self.turns / TURNS_PER_WATCH
+        ));
+    }
+
+    /// Puts the session back as it was at startup, keeping the loaded tables and clocks: time,
+    /// turns, the subtotal, recent rolls and undo history are cleared, and the current table
+    /// goes back to the default.
+    fn on_reset_command(&mut self) {
+        let mut clocks: Vec<String> = self.clocks.keys().cloned().collect();
+        clocks.sort();
+        for clock in clocks {
+            self.set_clock_minutes(clock, 0);
+        }
+        self.active_clock = None;
+        self.turns = 0;
+        self.total = 0;
+        self.recent_rolls.clear();
+        self.history = History::default();
+        self.update_scrollbacks([
+            "Reset the session: every clock is back to day 1, 00:00, on the default clock.",
+            "Cleared turns, the subtotal, recent rolls and undo history.",
+        ]);
+
+        self.previous_table = None;
+        self.current_table = None;
+        self.select_default_table();
+    }
+
+    /// Shows the loaded state at a glance: tables, current table, day and time, and aliases.
+    fn on_summary_command(&mut self) {
+        let (day, minutes) = self.day_and_minutes();
+        let mut lines = vec![
+            format!("Tables loaded: {}", self.tables.len()),
+            format!("Current table: {}", self.current_table.as_deref().unwrap_or("none")),
+            format!(
+                "Time: day {}, {} on the {} clock",
+                day,
+                self.format_time(minutes),
+                self.active_clock_name()
+            ),
+        ];
+        let format_aliases = |aliases: &HashMap<String, String>| {
+            let mut aliases: Vec<String> =
+                aliases.iter().map(|(alias, command)| format!("{} = {}", alias, command)).collect();
+            aliases.sort();
+            aliases.join(", ")
+        };
+        if self.aliases.is_empty() {
+            lines.push("Aliases: none".to_string());
+        } else {
+            lines.push(format!("Aliases: {}", format_aliases(&self.aliases)));
+        }
+        if let Some(table) = self.table()
+            && !table.aliases.is_empty()
+        {
+            lines.push(format!("{} aliases: {}", table.name, format_aliases(&table.aliases)));
+        }
+        self.update_scrollbacks(lines);
+    }
+
+    fn on_turn_command(&mut self) {
+        self.pass_turns(1);
+    }
+
+    fn on_watch_command(&mut self) {
+        self.pass_turns(TURNS_PER_WATCH);
+    }
+
+    fn add_minutes(&mut self, minutes: u32) {
+        self.advance_time(minutes, 0);
+    }
+
+    fn pass_turns(&mut self, turns: u32) {
+        self.advance_time(TURN_MINUTES * turns, turns);
+    }
+
+    /// Moves the active clock on by `minutes`, counting `turns` dungeon turns as passed.
+    fn advance_time(&mut self, minutes: u32, turns: u32) {
+        let time = self.current_time_minutes().saturating_add(minutes);
+        self.set_time_and_turns(time, turns);
+        self.update_scrollback(format!(
+            "Added {} minutes. New time: {}",
+            minutes,
+            self.format_time(time)
+        ));
+    }
+
+    /// Rolls `dice` from `rng` for how many minutes something took, and adds them.
+    fn add_rolled_minutes(&mut self, rng: &mut impl rand::Rng, dice: &str) {
+        let Some(roll) = api::roll_modified_with(rng, dice) else {
+            self.show_error(format!("Can't roll {} for the time taken.", dice));
+            return;
+        };
+        let minutes = roll.clamp(0, command::MAX_MINUTES as i64) as u32;
+        self.show_roll(format!("Rolled {} for time: {}", dice, roll));
+        self.emit(WaylineEvent::RollMade {
+            table: None,
+            dice: dice.to_string(),
+            roll: minutes,
+            result: None,
+        });
+        self.add_minutes(minutes);
+    }
+
+    /// Advances time, then rolls the current table for an encounter, if one is selected and
+    /// `check` (when given) succeeds.
+    fn on_travel_command(&mut self, minutes: u32, check: Option<String>) {
+        self.add_minutes(minutes);
+        if self.table().is_none() {
+            self.update_scrollback("No table selected, so no encounter roll.");
+            return;
+        }
+        match check {
+            Some(check) => self.on_check_command(&check, true),
+            None => self.on_roll_command(None, None, Vec::new()),
+        }
+    }
+
+    fn sub_minutes(&mut self, minutes: u32) {
+        let time = self.current_time_minutes().saturating_sub(minutes);
+        self.set_current_time_minutes(time);
+        self.update_scrollback(format!(
+            "Subtracted {} minutes. New time: {}",
+            minutes,
+            self.format_time(time)
+        ));
+    }
+
+    /// Advances the active clock to the next occurrence of a named time of day.
+    fn on_goto_command(&mut self, anchor: &str) {
+        let time_of_day = self.time_anchors.get(anchor).copied().or_else(|| {
+            DEFAULT_TIME_ANCHORS
+                .iter()
+                .find(|(name, _)| *name == anchor)
+                .map(|(_, minutes)| *minutes)
+        });
+        let Some(time_of_day) = time_of_day else {
+            self.show_error(format!("Unknown time of day: {}", anchor));
+            return;
+        };
+
+        let minutes = api::minutes_until(self.current_time_minutes(), time_of_day);
+        let time = self.current_time_minutes().saturating_add(minutes);
+        self.set_current_time_minutes(time);
+        self.update_scrollback(format!(
+            "Advanced {} minutes to {} ({:02}:{:02}).",
+            minutes,
+            anchor,
+            time_of_day / 60,
+            time_of_day % 60
+        ));
+    }
+
+    /// Moves the active clock to the same time on day `day`, or `day` days on (or back) if
+    /// `relative`. Day 1 is the clock's first.
+    fn on_set_day_command(&mut self, day: i64, relative: bool) {
+        let (today, time_of_day) = self.day_and_minutes();
+        let target = if relative { today as i64 + day } else { day };
+        if target < 1 {
+            self.show_error("There's no day before day 1.");
+            return;
+        }
+        let minutes = (target - 1)
+            .checked_mul(api::MINUTES_PER_DAY as i64)
+            .and_then(|start| u32::try_from(start + time_of_day as i64).ok());
+        let Some(minutes) = minutes else {
+            self.show_error(format!("Day {} is too far off.", target));
+            return;
+        };
+        self.set_current_time_minutes(minutes);
+        self.update_scrollback(format!("Now day {}, {}.", target, self.format_time(minutes)));
+    }
+
+    fn on_clock_new_command(&mut self, name: String) {
+        if name == DEFAULT_CLOCK || self.clocks.contains_key(&name) {
+            self.show_error(format!("Clock '{}' already exists.", name));
+            return;
+        }
+        self.clocks.insert(name.clone(), 0);
+        self.update_scrollback(format!("Created clock '{}'.", name));
+    }
+
+    /// Makes `name` the clock that `add`, `sub` and `time` work on.
+    fn on_clock_use_command(&mut self, name: String) {
+        if name == DEFAULT_CLOCK {
+            self.active_clock = None;
+        } else if self.clocks.contains_key(&name) {
+            self.active_clock = Some(name);
+        } else {
+            self.show_error(format!("Clock '{}' not found.", name));
+            return;
+        }
+        self.update_scrollback(format!("Switched to clock '{}'.", self.active_clock_name()));
+    }
+
+    fn on_clocks_command(&mut self) {
+        let mut names: Vec<&str> = self
+            .clocks
+            .keys()
+            .map(String::as_str)
+            .filter(|name| *name != DEFAULT_CLOCK)
+            .collect();
+        names.sort();
+        names.insert(0, DEFAULT_CLOCK);
+
+        let active = self.active_clock_name();
+        let lines: Vec<String> = names
+            .into_iter()
+            .map(|name| {
+                let time = self.clocks.get(name).copied().unwrap_or_default();
+                let marker = if name == active { "*" } else { "-" };
+                format!("{} {}: {}", marker, name, self.format_time(time))
+            })
+            .collect();
+        self.update_scrollbacks(lines);
+    }
+
+    fn on_enter_pressed(&mut self) -> Task<Message> {
+        let input = std::mem::take(&mut self.input);
+        self.history_cursor = None;
+        if input.trim().is_empty() {
+            return Task::none();
+        }
+        for line in input.lines().filter(|line| !is_blank_or_comment(line)) {
+            input_history::push(&mut self.input_history, line.trim());
+        }
+        if input.contains('\n') {
+            self.run_script(&input)
+        } else {
+            self.run_line(&input)
+        }
+    }
+
+    /// Runs each line of `script` as a command, in order.
+    /// Blank lines and lines starting with `#` are skipped.
+    fn run_script(&mut self, script: &str) -> Task<Message> {
+        let tasks: Vec<Task<Message>> = script
+            .lines()
+            .filter(|line| !is_blank_or_comment(line))
+            .map(|line| self.run_line(line.trim()))
+            .collect();
+        Task::batch(tasks)
+    }
+
+    /// Runs the config's `[startup]` commands, then lists any that reported an error.
+    fn run_startup_commands(&mut self) -> Task<Message> {
+        let commands = self.startup_commands.clone();
+        let mut tasks = Vec::new();
+        let mut failed = Vec::new();
+        for line in commands.iter().filter(|line| !is_blank_or_comment(line)) {
+            let before = self.scrollback.len();
+            tasks.push(self.run_line(line.trim()));
+            if self.scrollback[before..]
+                .iter()
+                .any(|line| line.kind == LineKind::Error)
+            {
+                failed.push(line.trim());
+            }
+        }
+
+        for line in failed {
+            self.show_error(format!("Startup command failed: {}", line));
+        }
+        Task::batch(tasks)
+    }
+
+    /// Runs the command bound to a key combination, if any, as if it had been entered. Up and
+    /// Down, unless bound, step through the commands entered before.
+    fn on_key_pressed(
+        &mut self,
+        key: &keyboard::Key,
+        modifiers: keyboard::Modifiers,
+    ) -> Task<Message> {
+        let combo = key_combo(key, modifiers);
+        let command = match self.keybindings.get(&combo) {
+            Some(command) => command.clone(),
+            None => match DEFAULT_KEYBINDINGS.iter().find(|(default, _)| *default == combo) {
+                Some((_, command)) => command.to_string(),
+                None => return self.recall_input(&combo),
+            },
+        };
+        if command.trim().is_empty() {
+            return Task::none();
+        }
+        self.run_line(&command)
+    }
+
+    /// Puts an earlier command in the input box for "arrowup", or a later one for "arrowdown";
+    /// past the newest, the box is cleared.
+    fn recall_input(&mut self, combo: &str) -> Task<Message> {
+        let cursor = match (combo, self.history_cursor) {
+            ("arrowup", Some(cursor)) => Some(cursor.saturating_sub(1)),
+            ("arrowup", None) => self.input_history.len().checked_sub(1),
+            ("arrowdown", Some(cursor)) => {
+                Some(cursor + 1).filter(|next| *next < self.input_history.len())
+            }
+            _ => return Task::none(),
+        };
+        self.history_cursor = cursor;
+        self.input = cursor.map(|cursor| self.input_history[cursor].clone()).unwrap_or_default();
+        text_input::move_cursor_to_end(MAIN_INPUT_ID)
+    }
+
+    /// Where `top` (or `bottom`) snaps the scrollback. While it's anchored to the bottom for
+    /// autoscroll, iced measures offsets up from the newest line, so the ends swap.
+    fn scroll_offset(&self, top: bool) -> scrollable::RelativeOffset {
+        if top == self.autoscroll_off {
+            scrollable::RelativeOffset::START
+        } else {
+            scrollable::RelativeOffset::END
+        }
+    }
+
+    /// Echoes `line` and runs it as a command. A `#` comment is echoed but not run.
+    fn run_line(&mut self, line: &str) -> Task<Message> {
+        self.push_line(LineKind::Echo, format!("{}{}", self.prompt(), line));
+        if line.trim_start().starts_with('#') {
+            return Task::none();
+        }
+        let line = self.expand_alias(line);
+        self.dispatch(command::parse_command(&line))
+    }
+
+    /// `line` with its first word replaced by what it's an alias for, if it is one: the
+    /// current table's `aliases` first, then the config's. Aliases aren't expanded again, so
+    /// one can't loop.
+    fn expand_alias(&self, line: &str) -> String {
+        let line = line.trim();
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let table_alias = self.table().and_then(|table| {
+            table
+                .aliases
+                .iter()
+                .find(|(alias, _)| alias.eq_ignore_ascii_case(word))
+                .map(|(_, command)| command)
+        });
+        match table_alias.or_else(|| self.aliases.get(&word.to_lowercase())) {
+            Some(command) if rest.is_empty() => command.clone(),
+            Some(command) => format!("{} {}", command, rest),
+            None => line.to_string(),
+        }
+    }
+
+    fn dispatch(&mut self, cmd: Command) -> Task<Message> {
+        match cmd {
+            Command::Reload => return reload_config(self.config_path()),
+            Command::Edit => {
+                let editor = std::env::var("EDITOR")
+                    .ok()
+                    .filter(|editor| !editor.trim().is_empty())
+                    .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+                let path = self.config_path().to_path_buf();
+                self.update_scrollback(format!("Editing {} with {}...", path.display(), editor));
+                return Task::perform(
+                    edit_file(editor, path.display().to_string()),
+                    Message::EditorClosed,
+                );
+            }
+            Command::Prompt(prompt) => {
+                self.update_scrollback(format!("Prompt set to '{}'.", prompt));
+                self.prompt = Some(prompt);
+            }
+            Command::RollTable(table_name_opt, dice_override, modifiers) => {
+                self.on_roll_command(table_name_opt, dice_override, modifiers)
+            }
+            Command::RollJson(table_name_opt, dice_override, modifiers, times) => {
+                self.on_roll_json_command(table_name_opt, dice_override, modifiers, times)
+            }
+            Command::RollUntil { target_entry, limit } => {
+                self.on_roll_until_command(&target_entry, limit)
+            }
+            Command::RollDice(dice_str, floor) => self.on_dice_command(&dice_str, floor),
+            Command::DiceHelp => self.update_scrollbacks(help::dice_notations()),
+            Command::SeededRoll { seed, dice } => self.on_seeded_roll_command(seed, &dice),
+            Command::Average { times, dice } => self.on_average_command(times, &dice),
+            Command::Sample { times, target } => self.on_sample_command(times, target),
+            Command::Peek(target) => self.on_peek_command(target),
+            Command::Force { table, row } => self.on_force_command(&table, row),
+            Command::RollMany { times, target, unique } => {
+                self.on_roll_many_command(times, target, unique)
+            }
+            Command::Oppose { left, right } => self.on_oppose_command(&left, &right),
+            Command::Gen(table_name) => self.on_gen_command(table_name),
+            Command::Total(dice_str) => self.on_total_command(&dice_str),
+            Command::TotalReset => {
+                self.total = 0;
+                self.update_scrollback("subtotal: 0");
+            }
+            Command::List(it) => self.on_list_command(it),
+            Command::Tables => self.on_tables_command(),
+            Command::RecentRolls => self.on_recent_rolls_command(),
+            Command::Compare(left, right) => self.on_compare_command(&left, &right),
+            Command::Odds(target) => self.on_odds_command(target),
+            Command::WhatIf { target, modifier } => self.on_what_if_command(target, modifier),
+            Command::Matrix(left, right) => {
+                self.on_matrix_command(&mut rand::rng(), &left, &right)
+            }
+            Command::FindTable(dice) => self.on_find_table_command(dice),
+            Command::More => self.on_more_command(),
+            Command::Last => match self.last_result.clone() {
+                Some(result) => self.show_result(result),
+                None => self.update_scrollback("No results yet."),
+            },
+            Command::Lookup(name) => self.on_lookup_command(&name),
+            Command::Ev(table_name) => self.on_ev_command(table_name),
+            Command::Describe(target) => self.on_describe_command(target),
+            Command::Check(check, then_roll) => self.on_check_command(&check, then_roll),
+            Command::Time => self.on_time_command(),
+            Command::Add(minutes) => self.add_minutes(minutes),
+            Command::AddRolled(dice) => self.add_rolled_minutes(&mut rand::rng(), &dice),
+            Command::Sub(minutes) => self.sub_minutes(minutes),
+            Command::Travel { minutes, check } => self.on_travel_command(minutes, check),
+            Command::ClockNew(name) => self.on_clock_new_command(name),
+            Command::ClockUse(name) => self.on_clock_use_command(name),
+            Command::Clocks => self.on_clocks_command(),
+            Command::Turn => self.on_turn_command(),
+            Command::Watch => self.on_watch_command(),
+            Command::Goto(anchor) => self.on_goto_command(&anchor),
+            Command::SetDay { day, relative } => self.on_set_day_command(day, relative),
+            Command::Help(topic) => self.on_help_command(topic),
+            Command::Clear => self.scrollback.clear(),
+            Command::Reset => self.on_reset_command(),
+            Command::Summary => self.on_summary_command(),
+            Command::ScrollTop => return scroll_to(self.scroll_offset(true)),
+            Command::ScrollBottom => return scroll_to(self.scroll_offset(false)),
+            Command::ToggleAutoscroll => {
+                self.autoscroll_off = !self.autoscroll_off;
+                let state = if self.autoscroll_off { "off" } else { "on" };
+                self.update_scrollback(format!("Autoscroll {}.", state));
+            }
+            Command::ToggleRollLog => {
+                self.roll_log_hidden = !self.roll_log_hidden;
+                let state = if self.roll_log_hidden { "hidden" } else { "shown" };
+                self.update_scrollback(format!("Roll log {}.", state));
+            }
+            Command::Version => self.update_scrollbacks([
+                format!("wayline {}", env!("CARGO_PKG_VERSION")),
+                env!("CARGO_PKG_DESCRIPTION").to_string(),
+            ]),
+            Command::Unknown(cmd) => {
+                self.show_error(format!("Unknown command: {}", cmd));
+            }
+            Command::Use(table_name) => self.on_use_command(&table_name),
+            Command::Back => self.on_back_command(),
+            Command::Undo => self.on_undo_command(),
+            Command::Redo => self.on_redo_command(),
+        }
+
+        Task::none()
+    }
+
+    /// Selects a table by name, or by its number in the last `list` of tables.
+    /// A table whose name is a number still wins over the index.
+    fn on_use_command(&mut self, table_name: &str) {
+        let key = match self.resolve_table_name(table_name) {
+            Some(key) => key.clone(),
+            None => match table_name.parse::<usize>() {
+                Ok(index) => {
+                    let Some(key) = index
+                        .checked_sub(1)
+                        .and_then(|index| self.listed_tables.get(index))
+                    else {
+                        self.show_error(format!(
+                            "No table #{} in the last listing ({} listed).",
+                            index,
+                            self.listed_tables.len()
+                        ));
+                        return;
+                    };
+                    if !self.tables.contains_key(key) {
+                        self.show_error(format!("Table '{}' no longer exists.", key));
+                        return;
+                    }
+                    key.clone()
+                }
+                Err(_) => {
+                    self.show_error(format!("Table '{}' not found.", table_name));
+                    return;
+                }
+            },
+        };
+
+        self.select_table(key);
+    }
+
+    /// Swaps back to the table that was selected before the current one.
+    fn on_back_command(&mut self) {
+        let Some(key) = self.previous_table.clone() else {
+            self.show_error("No previous table.");
+            return;
+        };
+        if !self.tables.contains_key(&key) {
+            self.show_error(format!("Table '{}' no longer exists.", key));
+            return;
+        }
+        self.select_table(key);
+    }
+
+    /// Makes `key`, which must be a loaded table, the current table, remembering the one it
+    /// replaces for `back`.
+    fn select_table(&mut self, key: String) {
+        self.update_scrollback(format!("Switched to table '{}'.", key));
+        self.emit(WaylineEvent::TableSwitched { table: key.clone() });
+        if let Some(description) = self.tables[&key].description.clone() {
+            self.update_scrollback(description);
+        }
+        if self.current_table.as_ref() != Some(&key) {
+            self.history.record(Action::TableSwitch {
+                before: self.current_table.clone(),
+                after: key.clone(),
+            });
+            self.previous_table = self.current_table.replace(key);
+        }
+    }
+
+    /// Rolls `dice_str`. A `floor` clamps the result up to that minimum rather than rerolling,
+    /// so the outcome is predictable; both the raw and clamped results are shown.
+    fn on_dice_command(&mut self, dice_str: &str, floor: Option<u32>) {
+        let Some(roll) = self.roll_or_report(dice_str) else {
+            return;
+        };
+        self.emit(WaylineEvent::RollMade {
+            table: None,
+            dice: dice_str.to_string(),
+            roll,
+            result: None,
+        });
+        match floor {
+            Some(floor) => self.show_roll(format!(
+                "Rolled {} min {}: {} (raw {})",
+                dice_str,
+                floor,
+                roll.max(floor),
+                roll
+            )),
+            None => self.show_roll(format!("Rolled {}: {}", dice_str, roll)),
+        }
+    }
+
+    /// Rolls `dice_str` on a generator seeded with `seed`, so anyone can reproduce the result.
+    /// Other rolls are unaffected.
+    fn on_seeded_roll_command(&mut self, seed: u64, dice_str: &str) {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        match api::roll_with(&mut rng, dice_str) {
+            Some(roll) => {
+                self.show_roll(format!("Rolled {} with seed {}: {}", dice_str, seed, roll))
+            }
+            // The same dice fail to roll unseeded too, which reports why.
+            None => {
+                let _ = self.roll_or_report(dice_str);
+            }
+        }
+    }
+
+    fn on_average_command(&mut self, times: u32, dice_str: &str) {
+        if let Ok(spec) = api::DiceSpec::parse(dice_str)
+            && spec.count as u64 * times as u64 > api::MAX_STATS_DICE
+        {
+            self.show_error(format!(
+                "Can't average {} rolls of {}: that's more than {} dice in all.",
+                times,
+                dice_str,
+                api::MAX_STATS_DICE
+            ));
+            return;
+        }
+        match api::roll_stats(&mut rand::rng(), dice_str, times) {
+            Some((mean, min, max)) => self.show_result(format!(
+                "Average of {} rolls of {}: {:.2} (min {}, max {})",
+                times, dice_str, mean, min, max
+            )),
+            // The same dice fail to roll once, too, which reports why.
+            None => {
+                let _ = self.roll_or_report(dice_str);
+            }
+        }
+    }
+
+    /// Rolls both sides of an opposed check and reports which won, and by how much.
+    fn on_oppose_command(&mut self, left: &str, right: &str) {
+        let mut rng = rand::rng();
+        let (Some(left_roll), Some(right_roll)) = (
+            api::roll_modified_with(&mut rng, left),
+            api::roll_modified_with(&mut rng, right),
+        ) else {
+            self.show_error(format!(
+                "Can't roll {} vs {}: each side must be dice, optionally with +N or -N.",
+                left, right
+            ));
+            return;
+        };
+
+        let outcome = match left_roll.cmp(&right_roll) {
+            std::cmp::Ordering::Greater => format!("left wins by {}", left_roll - right_roll),
+            std::cmp::Ordering::Less => format!("right wins by {}", right_roll - left_roll),
+            std::cmp::Ordering::Equal => "tie".to_string(),
+        };
+        self.show_roll(format!(
+            "{} ({}) vs {} ({}): {}",
+            left, left_roll, right, right_roll, outcome
+        ));
+    }
+
+    /// Rolls the named or current table `times` times and lists how often each result came
+    /// up, most frequent first.
+    fn on_sample_command(&mut self, times: u32, target: Option<String>) {
+        let table = match self.resolve_target(target.as_deref()) {
+            Ok(table) => table,
+            Err(e) => {
+                self.show_error(e);
+                return;
+            }
+        };
+
+        let mut counts: Vec<(Option<String>, u32)> =
+            api::tally(&mut rand::rng(), table, times).into_iter().collect();
+        counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+        let mut lines = vec![format!("{}, {} rolls:", table.name, times)];
+        for (result, count) in counts {
+            lines.push(format!(
+                "- {}: {} ({:.1}%)",
+                result.as_deref().unwrap_or("no matching entry"),
+                count,
+                count as f64 * 100.0 / times as f64
+            ));
+        }
+        self.show_results(lines);
+    }
+
+    fn on_total_command(&mut self, dice_str: &str) {
+        if let Some(roll) = self.roll_or_report(dice_str) {
+            self.emit(WaylineEvent::RollMade {
+                table: None,
+                dice: dice_str.to_string(),
+                roll,
+                result: None,
+            });
+            self.total = self.total.saturating_add(roll);
+            self.show_roll(format!("Rolled {}: {}, subtotal: {}", dice_str, roll, self.total));
+        }
+    }
+
+    /// Rolls `dice_str`, printing why if it can't be rolled.
+    fn roll_or_report(&mut self, dice_str: &str) -> Option<u32> {
+        let roll = api::roll(dice_str);
+        if roll.is_none() {
+            if let Err(e @ api::DiceError::BadWeights { .. }) = api::DiceSpec::parse(dice_str) {
+                self.show_error(format!("Invalid dice: {}", e));
+            } else if api::is_dice_notation(dice_str) {
+                self.show_error(format!(
+                    "Too many dice: {} (at most {} per roll)",
+                    dice_str,
+                    api::MAX_DICE
+                ));
+            } else {
+                self.show_error(format!("Invalid dice notation: {}", dice_str));
+            }
+        }
+        roll
+    }
+
+    /// Rolls on the named table, or the current one when no name is given.
+    /// A target that isn't a table name but looks like dice (e.g. `roll 1d20`) is rolled as dice;
+    /// a table that happens to be named like dice still wins.
+    /// `dice_override` rolls the table on those dice instead of its own.
+    fn on_roll_command(
+        &mut self,
+        target: Option<String>,
+        dice_override: Option<String>,
+        modifiers: Vec<String>,
+    ) {
+        if let Some(ref name) = target
+            && self.resolve_table_name(name).is_none()
+            && api::is_dice_notation(name)
+        {
+            self.on_dice_command(name, None);
+            return;
+        }
+
+        match self.roll_table(&mut rand::rng(), target, dice_override, &modifiers) {
+            Ok(outcome) => {
+                for warning in &outcome.warnings {
+                    warn!("Table {:?}: {}", outcome.table, warning);
+                }
+                let warnings: Vec<String> = outcome
+                    .warnings
+                    .iter()
+                    .map(|warning| format!("Warning: table '{}': {}.", outcome.table, warning))
+                    .collect();
+                self.show_roll(outcome.line);
+                self.update_scrollbacks(warnings);
+                self.emit(WaylineEvent::RollMade {
+                    table: Some(outcome.table),
+                    dice: outcome.dice,
+                    roll: outcome.roll,
+                    result: outcome.result,
+                });
+            }
+            Err(e) => self.show_error(e),
+        }
+    }
+
+    /// Rolls two tables from `rng` and shows their results together, e.g. "Forest + Wolves",
+    /// for systems that cross-reference one table against another.
+    fn on_matrix_command(&mut self, rng: &mut impl rand::Rng, left: &str, right: &str) {
+        let mut outcomes = Vec::new();
+        for name in [left, right] {
+            match self.roll_table(rng, Some(name.to_string()), None, &[]) {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(e) => {
+                    self.show_error(e);
+                    return;
+                }
+            }
+        }
+
+        let join = |part: fn(&RollOutcome) -> String| {
+            outcomes.iter().map(part).collect::<Vec<String>>()
+        };
+        let tables = join(|outcome| outcome.table.clone());
+        let rolls = join(|outcome| outcome.breakdown.clone());
+        let results = join(|outcome| {
+            outcome.result.clone().unwrap_or_else(|| "no matching entry".to_string())
+        });
+        let mut warnings = Vec::new();
+        for outcome in &outcomes {
+            for warning in &outcome.warnings {
+                warn!("Table {:?}: {}", outcome.table, warning);
+                warnings.push(format!("Warning: table '{}': {}.", outcome.table, warning));
+            }
+        }
+        self.show_roll(format!(
+            "{} -> ({}): rolled: {}",
+            tables.join(" + "),
+            rolls.join(", "),
+            results.join(" + ")
+        ));
+        self.update_scrollbacks(warnings);
+        for outcome in outcomes {
+            self.emit(WaylineEvent::RollMade {
+                table: Some(outcome.table),
+                dice: outcome.dice,
+                roll: outcome.roll,
+                result: outcome.result,
+            });
+        }
+    }
+
+    /// Rolls a table as `roll` does and prints the outcome as a single line of JSON, with any
+    /// warnings in it rather than after it, so the output can be piped elsewhere. With `times`,
+    /// prints an array of that many outcomes.
+    fn on_roll_json_command(
+        &mut self,
+        mut target: Option<String>,
+        dice_override: Option<String>,
+        modifiers: Vec<String>,
+        mut times: Option<u32>,
+    ) {
+        // A table whose name starts with a number, like "1001 Nights", is rolled once.
+        if let Some(count) = times {
+            let literal = match &target {
+                Some(name) => format!("{} {}", count, name),
+                None => count.to_string(),
+            };
+            if self.resolve_table_name(&literal).is_some() {
+                (target, times) = (Some(literal), None);
+            }
+        }
+
+        let mut rng = rand::rng();
+        let mut outcomes = Vec::new();
+        for _ in 0..times.map_or(1, |times| times.min(command::MAX_REPEAT_ROLLS)) {
+            match self.roll_table(&mut rng, target.clone(), dice_override.clone(), &modifiers) {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(e) => {
+                    self.show_error(e);
+                    return;
+                }
+            }
+        }
+        for outcome in &outcomes {
+            for warning in &outcome.warnings {
+                warn!("Table {:?}: {}", outcome.table, warning);
+            }
+        }
+        let json = match times {
+            Some(_) => serde_json::to_string(&outcomes),
+            None => serde_json::to_string(&outcomes[0]),
+        };
+        match json {
+            Ok(json) => self.show_result(json),
+            Err(e) => self.show_error(format!("Couldn't write the roll as JSON: {}", e)),
+        }
+        for outcome in outcomes {
+            self.emit(WaylineEvent::RollMade {
+                table: Some(outcome.table),
+                dice: outcome.dice,
+                roll: outcome.roll,
+                result: outcome.result,
+            });
+        }
+    }
+
+    /// Rolls a table for `roll`, drawing the dice from `rng`, and works out how to show the
+    /// result without showing it. Fails with the message to show if there's nothing to roll.
+    fn roll_table(
+        &self,
+        rng: &mut impl rand::Rng,
+        target: Option<String>,
+        dice_override: Option<String>,
+        modifiers: &[String],
+    ) -> Result<RollOutcome, String> {
+        // `roll hexes.weather` shows only the weather column; a table named with a dot wins.
+        let (target, column) = match target {
+            Some(ref name)
+                if self.resolve_table_name(name).is_none()
+                    && let Some((table_name, column)) = name.rsplit_once('.')
+                    && self.resolve_table_name(table_name).is_some() =>
+            {
+                (Some(table_name.to_string()), Some(column.to_string()))
+            }
+            target => (target, None),
+        };
+
+        let table = self.resolve_target(target.as_deref())?;
+
+        let column = match column {
+            Some(column) => match table.column(&column) {
+                Some(column) => Some(column.to_string()),
+                None => {
+                    return Err(format!("Table '{}' has no column '{}'.", table.name, column));
+                }
+            },
+            None => None,
+        };
+
+        let table_name = table.name.clone();
+        let dice = dice_override.unwrap_or_else(|| table.dice.clone());
+
+        let mut warnings = Vec::new();
+        let mut modifier = 0i64;
+        let mut applied = Vec::new();
+        for name in modifiers {
+            match table.modifier(name) {
+                Some((key, value)) => {
+                    modifier += value as i64;
+                    applied.push(format!("{:+} {}", value, key));
+                }
+                None => warnings.push(format!("no modifier '{}', rolled without it", name)),
+            }
+        }
+
+        let (mut roll, rolls, mut result) = api::roll_on_with(rng, table, &dice);
+        let mut shown_roll = format_roll(&dice, roll, &rolls);
+        // Only the first roll can be rerolled, so a reroll that comes up the same stands.
+        if !api::is_weighted(&dice) && table.reroll_on.contains(&roll) {
+            let (reroll, rerolls, reroll_result) = api::roll_on_with(rng, table, &dice);
+            let shown_reroll = format_roll(&dice, reroll, &rerolls);
+            shown_roll = format!("{} rerolled, {}", shown_roll, shown_reroll);
+            roll = reroll;
+            result = reroll_result;
+        }
+        if !applied.is_empty() {
+            if api::is_weighted(&dice) {
+                warnings.push("modifiers don't apply to weighted rolls".to_string());
+            } else {
+                let modified = (roll as i64 + modifier).clamp(0, u32::MAX as i64) as u32;
+                shown_roll = format!("{} {} = {}", shown_roll, applied.join(" "), modified);
+                roll = modified;
+                result = api::find_entry(table, roll);
+            }
+        }
+
+        let entry_name = match result {
+            Some(entry) => {
+                let (entry_name, interpolation_warnings) = api::interpolate(&entry.name);
+                warnings.extend(interpolation_warnings);
+                entry_name
+            }
+            None => String::new(),
+        };
+
+        let line = match result {
+            Some(entry) if let Some(column) = column => {
+                let value = entry.columns.get(&column).map(String::as_str).unwrap_or("-");
+                format!("{}.{} -> ({}): rolled: {}", table_name, column, shown_roll, value)
+            }
+            Some(entry) => format!(
+                "{} -> ({}): rolled: {}",
+                table_name,
+                shown_roll,
+                format_entry(table, entry, &entry_name, &mut warnings)
+            ),
+            None => match &table.fallback {
+                Some(fallback) => {
+                    format!("{} -> ({}): rolled: {}", table_name, shown_roll, fallback)
+                }
+                None => format!("{} -> ({}): no matching entry found.", table_name, shown_roll),
+            },
+        };
+        let entry = result.map(|_| entry_name);
+        Ok(RollOutcome {
+            table: table_name,
+            dice,
+            roll,
+            breakdown: shown_roll,
+            result: entry.clone().or_else(|| table.fallback.clone()),
+            entry,
+            line,
+            warnings,
+        })
+    }
+
+    /// Shows a table's `row`th entry, counting from 1, as a roll would show it. Nothing is
+    /// rolled, so it isn't kept for `last` or logged as an event.
+    fn on_force_command(&mut self, target: &str, row: usize) {
+        let Some(table) = self.find_table(target) else {
+            self.show_error(format!("Table '{}' not found.", target));
+            return;
+        };
+        let Some(entry) = row.checked_sub(1).and_then(|index| table.rows.get(index)) else {
+            self.show_error(format!(
+                "Table '{}' has no row {}; it has {} rows.",
+                table.name,
+                row,
+                table.rows.len()
+            ));
+            return;
+        };
+
+        let (entry_name, mut warnings) = api::interpolate(&entry.name);
+        let line = format!(
+            "{} -> (forced row {}): rolled: {}",
+            table.name,
+            row,
+            format_entry(table, entry, &entry_name, &mut warnings)
+        );
+        let warnings: Vec<String> = warnings
+            .into_iter()
+            .map(|warning| format!("Warning: table '{}': {}.", table.name, warning))
+            .collect();
+        self.push_line(LineKind::Result, line);
+        self.update_scrollbacks(warnings);
+    }
+
+    /// Rolls a table to see what would come up, without it counting as a roll: it isn't kept
+    /// for `last`, added to the roll log or logged as an event.
+    fn on_peek_command(&mut self, target: Option<String>) {
+        let table = match self.resolve_target(target.as_deref()) {
+            Ok(table) => table,
+            Err(e) => {
+                self.show_error(e);
+                return;
+            }
+        };
+
+        let (roll, rolls, result) = api::roll_on(table, &table.dice);
+        let shown_roll = format_roll(&table.dice, roll, &rolls);
+        let shown_result = result
+            .map(|entry| api::interpolate(&entry.name).0)
+            .or_else(|| table.fallback.clone());
+        let line = match shown_result {
+            Some(name) => format!("(peek) {} -> ({}): rolled: {}", table.name, shown_roll, name),
+            None => format!("(peek) {} -> ({}): no matching entry found.", table.name, shown_roll),
+        };
+        self.push_line(LineKind::Result, line);
+    }
+
+    /// Rolls a table `times` times. With `unique`, a result that already came up is rerolled,
+    /// giving up once `MAX_UNIQUE_REROLLS` rerolls haven't found enough different ones.
+    fn on_roll_many_command(&mut self, times: u32, target: Option<String>, unique: bool) {
+        // A table whose name starts with a number, e.g. "100 Rumours", wins over the count.
+        let literal = [Some(times.to_string()), target.clone(), unique.then(|| "unique".into())]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>()
+            .join(" ");
+        if self.resolve_table_name(&literal).is_some() {
+            self.on_roll_command(Some(literal), None, Vec::new());
+            return;
+        }
+
+        let table = match self.resolve_target(target.as_deref()) {
+            Ok(table) => table,
+            Err(e) => {
+                self.show_error(e);
+                return;
+            }
+        };
+
+        let times = times.min(command::MAX_REPEAT_ROLLS) as usize;
+        let mut lines = Vec::new();
+        let mut warnings = Vec::new();
+        let mut events = Vec::new();
+        let mut seen = HashSet::new();
+        let mut rerolls = 0;
+        while lines.len() < times {
+            let (roll, rolls, result) = api::roll_on(table, &table.dice);
+            let result_name = result.map(|entry| entry.name.as_str()).or(table.fallback.as_deref());
+            if unique && !seen.insert(result_name) {
+                if rerolls == MAX_UNIQUE_REROLLS {
+                    warnings.push(format!(
+                        "Only {} different results on '{}' after {} rerolls.",
+                        lines.len(),
+                        table.name,
+                        rerolls
+                    ));
+                    break;
+                }
+                rerolls += 1;
+                continue;
+            }
+
+            let shown_roll = format_roll(&table.dice, roll, &rolls);
+            let (shown_result, quantity) = match result {
+                Some(entry) => {
+                    let (entry_name, mut entry_warnings) = api::interpolate(&entry.name);
+                    let quantity = roll_quantity(table, entry, &mut entry_warnings);
+                    warnings.extend(entry_warnings.into_iter().map(|warning| {
+                        format!("Warning: table '{}': {}.", table.name, warning)
+                    }));
+                    (Some(entry_name), quantity)
+                }
+                None => (table.fallback.clone(), String::new()),
+            };
+            match &shown_result {
+                Some(name) => lines.push(format!(
+                    "{} -> ({}): rolled: {}{}",
+                    table.name, shown_roll, name, quantity
+                )),
+                None => lines.push(format!(
+                    "{} -> ({}): no matching entry found.",
+                    table.name, shown_roll
+                )),
+            }
+            events.push(WaylineEvent::RollMade {
+                table: Some(table.name.clone()),
+                dice: table.dice.clone(),
+                roll,
+                result: shown_result,
+            });
+        }
+
+        self.show_rolls(lines);
+        self.update_scrollbacks(warnings);
+        for event in events {
+            self.emit(event);
+        }
+    }
+
+    /// Rolls the current table until it produces the entry named `target` (ignoring case),
+    /// giving up after `limit` rolls.
+    fn on_roll_until_command(&mut self, target: &str, limit: u32) {
+        let Some(table) = self.table() else {
+            self.show_error("No table selected.");
+            return;
+        };
+        if !table.rows.iter().any(|entry| entry.name.to_lowercase() == target) {
+            self.show_error(format!("No entry named '{}' in '{}'.", target, table.name));
+            return;
+        }
+
+        let table_name = table.name.clone();
+        let dice = table.dice.clone();
+        for attempt in 1..=limit.max(1) {
+            let (roll, _, result) = api::roll_on(table, &dice);
+            if let Some(entry) = result
+                && entry.name.to_lowercase() == target
+            {
+                let entry_name = entry.name.clone();
+                self.show_roll(format!(
+                    "{} -> ({}): rolled: {} after {} attempt(s)",
+                    table_name, roll, entry_name, attempt
+                ));
+                self.emit(WaylineEvent::RollMade {
+                    table: Some(table_name),
+                    dice,
+                    roll,
+                    result: Some(entry_name),
+                });
+                return;
+            }
+        }
+        self.show_error(format!(
+            "{}: no '{}' in {} attempt(s).",
+            table_name, target, limit.max(1)
+        ));
+    }
+
+    /// Rolls on the named or current table, then on each rolled entry's subtable in turn,
+    /// printing the chain as a tree indented by depth.
+    fn on_gen_command(&mut self, target: Option<String>) {
+        let mut next = match target {
+            Some(name) => Some(name),
+            None => match &self.current_table {
+                Some(name) => Some(name.clone()),
+                None => {
+                    self.show_error("No table selected.");
+                    return;
+                }
+            },
+        };
+
+        let max_depth = self.max_table_depth.unwrap_or(DEFAULT_MAX_TABLE_DEPTH);
+        let mut lines = Vec::new();
+        for depth in 0..=max_depth {
+            let Some(name) = next.take() else {
+                break;
+            };
+            let indent = "  ".repeat(depth);
+            if depth == max_depth {
+                lines.push(format!(
+                    "{}Recursion limit reached: '{}' would be {} tables deep (max {}).",
+                    indent,
+                    name,
+                    depth + 1,
+                    max_depth
+                ));
+                break;
+            }
+            let Some(table) = self.find_table(&name) else {
+                lines.push(format!("{}Table '{}' not found.", indent, name));
+                break;
+            };
+
+            let (roll, rolls, result) = api::roll_on(table, &table.dice);
+            let shown_roll = format_roll(&table.dice, roll, &rolls);
+            match result {
+                Some(entry) => {
+                    lines.push(format!(
+                        "{}{} -> ({}): {}",
+                        indent, table.name, shown_roll, entry.name
+                    ));
+                    next = entry.subtable.clone();
+                }
+                None => lines.push(format!(
+                    "{}{} -> ({}): {}",
+                    indent,
+                    table.name,
+                    shown_roll,
+                    table.fallback.as_deref().unwrap_or("no matching entry found.")
+                )),
+            }
+        }
+        self.show_rolls(lines);
+    }
+
+    fn emit(&mut self, event: WaylineEvent) {
+        if let WaylineEvent::RollMade { table, dice, roll, result } = &event {
+            if self.recent_rolls.len() == MAX_RECENT_ROLLS {
+                self.recent_rolls.pop_front();
+            }
+            self.recent_rolls.push_back(RecentRoll {
+                source: table.clone().unwrap_or_else(|| dice.clone()),
+                roll: *roll,
+                result: result.clone(),
+                from_table: table.is_some(),
+            });
+        }
+        if let Some(log) = &mut self.event_log
+            && let Err(e) = log.write(&event)
+        {
+            error!("{}", e);
+        }
+    }
+
+    fn update_scrollback<S: Into<String>>(&mut self, new_line: S) {
+        self.push_line(LineKind::Info, new_line);
+    }
+
+    fn show_error<S: Into<String>>(&mut self, new_line: S) {
+        self.push_line(LineKind::Error, new_line);
+    }
+
+    fn show_result<S: Into<String>>(&mut self, new_line: S) {
+        let new_line = new_line.into();
+        self.last_result = Some(new_line.clone());
+        self.push_line(LineKind::Result, new_line);
+    }
+
+    /// Shows a roll's result, in the roll log panel as well as the scrollback.
+    fn show_roll<S: Into<String>>(&mut self, new_line: S) {
+        let new_line = new_line.into();
+        self.show_result(new_line.clone());
+        if self.headless {
+            return;
+        }
+        self.roll_log.push(new_line);
+        if self.roll_log.len() > MAX_ROLL_LOG {
+            self.roll_log.remove(0);
+        }
+        self.roll_log_content = text_editor::Content::with_text(&self.roll_log.join("\n"));
+        self.roll_log_content
+            .perform(text_editor::Action::Move(text_editor::Motion::DocumentEnd));
+    }
+
+    fn show_rolls<I, S>(&mut self, new_lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for line in new_lines {
+            self.show_roll(line);
+        }
+    }
+
+    fn show_results<I, S>(&mut self, new_lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for line in new_lines {
+            self.show_result(line);
+        }
+    }
+
+    /// Starts appending every line of output to the file at `path`. If it can't be opened,
+    /// output stays in the scrollback only.
+    fn open_transcript(&mut self, path: &str) {
+        match Transcript::open(path) {
+            Ok(transcript) => self.transcript = Some(transcript),
+            Err(e) => {
+                error!("{}", e);
+                self.show_error(format!("{}; output won't be saved.", e));
+            }
+        }
+    }
+
+    fn push_line<S: Into<String>>(&mut self, kind: LineKind, new_line: S) {
+        if self.quiet && !self.started && kind == LineKind::Info {
+            return;
+        }
+        let text = new_line.into();
+        if let Some(transcript) = &mut self.transcript
+            && let Err(e) = transcript.write_line(&text)
+        {
+            // Warned about once: the log file is dropped rather than failing on every line.
+            error!("{}", e);
+            self.transcript = None;
+            self.show_error(format!("{}; no longer saving output.", e));
+        }
+        if self.headless {
+            self.headless_failed |= kind == LineKind::Error;
+            println!("{}", text);
+            return;
+        }
+        self.scrollback.push(ScrollbackLine { kind, text });
+    }
+
+    fn update_scrollbacks<I, S>(&mut self, new_lines: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for line in new_lines {
+            self.update_scrollback(line);
+        }
+    }
+}
+
+/// Reads the config file on the executor's thread pool so a slow disk doesn't stall the UI.
+fn load_config(path: &Path) -> Task<Message> {
+    Task::perform(read_config(path.to_path_buf()), Message::ConfigLoaded)
+}
+
+fn reload_config(path: &Path) -> Task<Message> {
+    Task::perform(read_config(path.to_path_buf()), Message::ConfigReloaded)
+}
+
+async fn read_config(path: PathBuf) -> Result<String, String> {
+    std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))
+}
+
+/// Wayline's directory among the user's config files, e.g. `~/.config/wayline` on Linux.
+fn user_config_dir() -> Option<PathBuf> {
+    directories::ProjectDirs::from("", "", CONFIG_DIR_NAME)
+        .map(|dirs| dirs.config_dir().to_path_buf())
+}
+
+/// Where to read the config from: `explicit` (from `--config`) if given, else `CONFIG_PATH`
+/// in the working directory, else `CONFIG_PATH` in `user_config_dir`. Falls back to the
+/// working directory's when neither file exists, so that's the one reported missing.
+fn resolve_config_path(
+    explicit: Option<PathBuf>,
+    user_config_dir: Option<PathBuf>,
+    exists: impl Fn(&Path) -> bool,
+) -> PathBuf {
+    if let Some(path) = explicit {
+        return path;
+    }
+    let local = PathBuf::from(CONFIG_PATH);
+    if exists(&local) {
+        return local;
+    }
+    match user_config_dir.map(|dir| dir.join(CONFIG_PATH)) {
+        Some(user) if exists(&user) => user,
+        _ => local,
+    }
+}
+
+/// Runs `editor` on `path` and waits for it to exit. `editor` may include arguments, as in
+/// `code --wait`. The wait happens on its own thread so the window stays responsive.
+async fn edit_file(editor: String, path: String) -> Result<(), String> {
+    let (sender, receiver) = iced::futures::channel::oneshot::channel();
+    std::thread::spawn(move || {
+        let mut words = editor.split_whitespace();
+        let program = words.next().unwrap_or(DEFAULT_EDITOR);
+        let result = match std::process::Command::new(program).args(words).arg(&path).status() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => {
+                Err(format!("Editor '{}' exited with {}; not reloading.", editor, status))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(format!("Editor '{}' not found; set $EDITOR to one that's installed.", editor))
+            }
+            Err(e) => Err(format!("Couldn't run editor '{}': {}", editor, e)),
+        };
+        let _ = sender.send(result);
+    });
+    receiver
+        .await
+        .unwrap_or_else(|_| Err("The editor stopped unexpectedly.".to_string()))
+}
+
+/// Shows a roll of several dice as its parts and total, e.g. `4+3=7`, and anything else as just
+/// the total. `d66` results already show their dice as digits.
+fn format_roll(dice: &str, total: u32, rolls: &[u32]) -> String {
+    if rolls.len() < 2 || api::digit_dice(dice).is_some() {
+        return total.to_string();
+    }
+    let parts: Vec<String> = rolls.iter().map(|roll| roll.to_string()).collect();
+    format!("{}={}", parts.join("+"), total)
+}
+
+/// The key combination for a key press in `config::normalize_key`'s form, e.g. `ctrl+r`.
+fn key_combo(key: &keyboard::Key, modifiers: keyboard::Modifiers) -> String {
+    let mut parts = Vec::new();
+    for (held, name) in [
+        (modifiers.control(), "ctrl"),
+        (modifiers.alt(), "alt"),
+        (modifiers.shift(), "shift"),
+        (modifiers.logo(), "logo"),
+    ] {
+        if held {
+            parts.push(name.to_string());
+        }
+    }
+    parts.push(match key {
+        keyboard::Key::Named(named) => format!("{:?}", named).to_lowercase(),
+        keyboard::Key::Character(c) => c.to_lowercase(),
+        keyboard::Key::Unidentified => String::new(),
+    });
+    parts.join("+")
+}
+
+/// The results that give `entry`, e.g. `[1, 2]`, with its range after any numbers, e.g.
+/// `[1, 10+]` or `[3-5]`.
+fn format_numbers(entry: &table::Entry) -> String {
+    let mut parts: Vec<String> = entry.numbers.iter().map(u32::to_string).collect();
+    match (entry.min, entry.max) {
+        (Some(min), None) => parts.push(format!("{}+", min)),
+        (None, Some(max)) => parts.push(format!("up to {}", max)),
+        (Some(min), Some(max)) => parts.push(format!("{}-{}", min, max)),
+        (None, None) => {}
+    }
+    format!("[{}]", parts.join(", "))
+}
+
+/// Scrolls the scrollback to `offset`, from `Wayline::scroll_offset`.
+fn scroll_to(offset: scrollable::RelativeOffset) -> Task<Message> {
+    scrollable::snap_to(scrollable::Id::new(SCROLLBACK_ID), offset)
+}
+
+/// A rolled entry as shown after "rolled: ", from its already interpolated name, with its
+/// quantity, columns and details.
+fn format_entry(
+    table: &table::Table,
+    entry: &table::Entry,
+    entry_name: &str,
+    warnings: &mut Vec<String>,
+) -> String {
+    format!(
+        "{}{}{}{}",
+        entry_name,
+        roll_quantity(table, entry, warnings),
+        format_columns(table, entry),
+        format_details(table, entry)
+    )
+}
+
+/// How many of `entry` appear, as ` ×5 (2d4)`, rolled from its or its table's `quantity`, or
+/// nothing if neither has one. A plain number is shown as it is.
+fn roll_quantity(table: &table::Table, entry: &table::Entry, warnings: &mut Vec<String>) -> String {
+    let Some(quantity) = table.quantity_for(entry) else {
+        return String::new();
+    };
+    if let Ok(count) = quantity.parse::<u32>() {
+        return format!(" ×{}", count);
+    }
+    match api::roll(&quantity.to_lowercase()) {
+        Some(count) => format!(" ×{} ({})", count, quantity),
+        None => {
+            warnings.push(format!("can't roll quantity {} for '{}'", quantity, entry.name));
+            String::new()
+        }
+    }
+}
+
+/// An entry's value for each of the table's columns as ` [column: value, ...]`, or nothing if
+/// the table has no columns.
+fn format_columns(table: &table::Table, entry: &table::Entry) -> String {
+    if table.columns.is_empty() {
+        return String::new();
+    }
+    let values: Vec<String> = table
+        .columns
+        .iter()
+        .map(|column| {
+            let value = entry.columns.get(column).map(String::as_str).unwrap_or("-");
+            format!("{}: {}", column, value)
+        })
+        .collect();
+    format!(" [{}]", values.join(", "))
+}
+
+/// An entry's extra details, including any it `see`s, as ` (key: value, ...)` sorted by key,
+/// or nothing if it has none.
+fn format_details(table: &table::Table, entry: &table::Entry) -> String {
+    let details = table.details_for(entry);
+    if details.is_empty() {
+        return String::new();
+    }
+    let mut details: Vec<String> = details
+        .iter()
+        .map(|(key, value)| match value {
+            toml::Value::String(s) => format!("{}: {}", key, s),
+            value => format!("{}: {}", key, value),
+        })
+        .collect();
+    details.sort();
+    format!(" ({})", details.join(", "))
+}
+
+fn is_blank_or_comment(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+fn get_table_info(table: &table::Table) -> Vec<String> {
+    let mut lines: Vec<String> = vec![format!("Table: {}", table.name)];
+    if let Some(description) = &table.description {
+        lines.push(description.clone());
+    }
+    match api::dice_range(&table.dice) {
+        Some(range) => lines.push(format!(
+            "Range: {}–{} ({})",
+            range.start(),
+            range.end(),
+            table.dice
+        )),
+        None => lines.push(format!("Dice: {}", table.dice)),
+    }
+    if let Some(author) = &table.author {
+        lines.push(format!("Author: {}", author));
+    }
+    if let Some(source) = &table.source {
+        lines.push(format!("Source: {}", source));
+    }
+    if let Some(edition) = &table.edition {
+        lines.push(format!("Edition: {}", edition));
+    }
+    if api::is_weighted(&table.dice) {
+        let probabilities = api::weighted_probabilities(table);
+        for (entry, p) in table.rows.iter().zip(probabilities) {
+            lines.push(format!("- {}: {:.1}%{}", entry.name, p * 100.0, disabled_marker(entry)));
+        }
+    } else {
+        for entry in &table.rows {
+            match &entry.subtable {
+                Some(subtable) => lines.push(format!(
+                    "- {}: {} -> {}{}",
+                    entry.name,
+                    format_numbers(entry),
+                    subtable,
+                    disabled_marker(entry)
+                )),
+                None => lines.push(format!(
+                    "- {}: {}{}",
+                    entry.name,
+                    format_numbers(entry),
+                    disabled_marker(entry)
+                )),
+            }
+        }
+    }
+    lines
+}
+
+fn disabled_marker(entry: &table::Entry) -> &'static str {
+    if entry.enabled { "" } else { " (disabled)" }
+}
+
+/// "toml" or "json" if `path` has that extension, ignoring case.
+fn table_file_kind(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?;
+    ["toml", "json"].into_iter().find(|kind| extension.eq_ignore_ascii_case(kind))
+}
+
+/// Validates the tables in `path`, and those it includes, without opening a window: prints
+/// each problem found and fails if there were any, or prints "OK".
+fn run_check(path: &Path) -> ExitCode {
+    let problems = match std::fs::read_to_string(path) {
+        Ok(source) => check_tables(path, &source),
+        Err(e) => vec![format!("{}: {}", path.display(), e)],
+    };
+    if problems.is_empty() {
+        println!("OK");
+        return ExitCode::SUCCESS;
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    println!("{} problem{} found.", problems.len(), if problems.len() == 1 { "" } else { "s" });
+    ExitCode::FAILURE
+}
+
+/// Every problem with the tables in `source`, read from `path`, and in the files it includes:
+/// tables that don't parse, then what `api::validate_table` finds in the rest. They're loaded
+/// just as the app loads them, so the two find the same problems.
+fn check_tables(path: &Path, source: &str) -> Vec<String> {
+    // Quiet, since `run_check` prints the summary's warnings itself.
+    let mut w = Wayline { headless: true, quiet: true, ..Wayline::default() };
+    let summary = if table_file_kind(path) == Some("json") {
+        let mut summary = LoadSummary::default();
+        w.load_json_tables(path, source, &mut summary);
+        summary
+    } else {
+        w.load_all_from(path, source)
+    };
+    summary.failures.into_iter().chain(summary.warnings).collect()
+}
+
+/// Runs a single command such as `roll monsters` against the config and prints the result,
+/// without opening a window.
+fn run_headless(input: &str, args: &Args, event_log: Option<EventLog>) -> ExitCode {
+    let mut w = Wayline {
+        headless: true,
+        quiet: args.quiet,
+        event_log,
+        no_config: args.no_config,
+        config_path: Some(resolve_config_path(
+            args.config.clone(),
+            user_config_dir(),
+            Path::exists,
+        )),
+        ..Wayline::default()
+    };
+    if let Some(path) = &args.log_file {
+        w.open_transcript(path);
+    }
+    // On stderr, so the command's own output can still be piped, e.g. from `roll --json`.
+    if !args.no_config && !args.quiet {
+        eprintln!("Reading config from {}.", w.config_path().display());
+    }
+
+    match std::fs::read_to_string(w.config_path()) {
+        _ if args.no_config => {}
+        Ok(config) => {
+            let summary = w.load_all(&config);
+            if !summary.failures.is_empty() {
+                w.report_load("Loaded", &summary);
+            }
+        }
+        Err(e) => error!("Failed to read config file {}: {}", w.config_path().display(), e),
+    }
+    if let Some(dir) = &args.tables_dir {
+        match w.load_tables_dir(dir) {
+            Ok(results) => {
+                for (path, summary) in results {
+                    for failure in summary.failures {
+                        w.show_error(format!("Failed to load {}: {}", path.display(), failure));
+                    }
+                }
+            }
+            Err(e) => w.show_error(format!("Can't read tables directory {}: {}", dir.display(), e)),
+        }
+    }
+
+    w.started = true;
+    let cmd = command::parse_command(input);
+    let unknown = matches!(cmd, Command::Unknown(_));
+    w.headless_failed = false;
+    // Nothing runs the returned task here; `reload` has no effect outside the window.
+    let _ = w.dispatch(cmd);
+    if unknown || w.headless_failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+pub fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut event_log = None;
+    if let Some(path) = &args.log {
+        match EventLog::open(path) {
+            Ok(log) => event_log = Some(log),
+            Err(e) => {
+                eprintln!("{}", e);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    if let Some(path) = &args.check {
+        return run_check(path);
+    }
+    if !args.command.is_empty() {
+        return run_headless(&args.command.join(" "), &args, event_log);
+    }
+
+    iced::application(Wayline::title, Wayline::update, Wayline::view)
+        .theme(Wayline::theme)
+        .subscription(Wayline::subscription)
+        .run_with(move || Wayline::new(args, event_log))
+        .expect("unable to run application");
+    ExitCode::SUCCESS
+}
+
+/// The built-in iced theme called `name`, ignoring case, spaces and dashes, so "tokyo-night"
+/// finds "Tokyo Night".
+fn theme_named(name: &str) -> Option<iced::Theme> {
+    let simplify = |name: &str| {
+        name.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_lowercase()
+    };
+    iced::Theme::ALL
+        .iter()
+        .find(|theme| simplify(&theme.to_string()) == simplify(name))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_TABLES: &str = r#"
+        [[table]]
+        name = "Monsters"
+        dice = "1d1"
+        [[table.rows]]
+        name = "Goblin"
+        numbers = [1]
+
+        [[table]]
+        name = "1d20"
+        dice = "1d1"
+        priority = true # Goblin shares 1 with Not Dice, for rolls with other dice
+        [[table.rows]]
+        name = "Not Dice"
+        numbers = [1]
+        [[table.rows]]
+        name = "Goblin"
+        numbers = [1, 2]
+    "#;
+
+    fn last_line(w: &Wayline) -> &str {
+        w.scrollback.last().map(|line| line.text.as_str()).unwrap_or_default()
+    }
+
+    fn submit(w: &mut Wayline, input: &str) {
+        w.input = input.to_string();
+        let _ = w.on_enter_pressed();
+    }
+
+    fn last_lines(w: &Wayline, count: usize) -> Vec<&str> {
+        w.scrollback[w.scrollback.len() - count..]
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_roll_dice_shortcut() {
+        let mut w = Wayline::default();
+        w.on_roll_command(Some("1d20".to_string()), None, Vec::new());
+        assert!(last_line(&w).starts_with("Rolled 1d20: "));
+
+        w.load_all(TEST_TABLES);
+        w.on_roll_command(Some("monsters".to_string()), None, Vec::new());
+        assert_eq!(last_line(&w), "Monsters -> (1): rolled: Goblin");
+    }
+
+    #[test]
+    fn test_roll_with_dice_override() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        let _ = w.dispatch(command::parse_command("roll monsters with 2d1"));
+        assert_eq!(last_line(&w), "Monsters -> (1+1=2): no matching entry found.");
+    }
+
+    #[test]
+    fn test_turn_and_watch() {
+        let mut w = Wayline::default();
+        let _ = w.dispatch(Command::Turn);
+        assert_eq!(w.current_time_minutes(), 10);
+        assert_eq!(w.turns, 1);
+
+        let _ = w.dispatch(Command::Watch);
+        assert_eq!(w.current_time_minutes(), 10 + 240);
+        assert_eq!(w.turns, 25);
+
+        w.on_time_command();
+        assert_eq!(last_line(&w), "Turns: 25, watches: 1");
+    }
+
+    #[test]
+    fn test_check_then_roll() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        w.current_table = Some("Monsters".to_string());
+
+        let _ = w.dispatch(command::parse_command("check 1d1<1 then roll"));
+        assert_eq!(last_line(&w), "Check 1d1<1: rolled 1, failed.");
+
+        let _ = w.dispatch(command::parse_command("check 1d1<=1 then roll"));
+        assert_eq!(
+            last_lines(&w, 2),
+            ["Check 1d1<=1: rolled 1, success.", "Monsters -> (1): rolled: Goblin"]
+        );
+    }
+
+    #[test]
+    fn test_reload_keeps_current_table() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        w.current_table = Some("Monsters".to_string());
+
+        w.reload_tables(TEST_TABLES);
+        assert_eq!(w.current_table.as_deref(), Some("Monsters"));
+
+        w.reload_tables("");
+        assert!(w.tables.is_empty());
+        assert_eq!(w.current_table, None);
+        assert_eq!(last_line(&w), "Table 'Monsters' no longer exists.");
+    }
+
+    #[test]
+    fn test_scrollback_line_kinds() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "roll monsters");
+        assert_eq!(w.scrollback[0].kind, LineKind::Echo);
+        assert_eq!(w.scrollback[1].kind, LineKind::Result);
+
+        let _ = w.dispatch(command::parse_command("bogus"));
+        assert_eq!(w.scrollback.last().map(|line| line.kind), Some(LineKind::Error));
+    }
+
+    #[test]
+    fn test_prompt() {
+        let mut w = Wayline::default();
+        submit(&mut w, "time");
+        assert_eq!(w.scrollback[0].text, "> time");
+
+        w.load_all("[ui]\nprompt = \"] \"");
+        submit(&mut w, "time");
+        assert_eq!(w.scrollback[w.scrollback.len() - 3].text, "] time");
+
+        let _ = w.dispatch(command::parse_command("prompt"));
+        submit(&mut w, "time");
+        assert_eq!(w.scrollback[w.scrollback.len() - 3].text, "time");
+    }
+
+    #[test]
+    fn test_table_name_case_matching() {
+        const GEMS: &str = r#"
+            [[table]]
+            name = "Gems"
+            dice = "1d1"
+            rows = []
+
+            [[table]]
+            name = "GEMS"
+            dice = "1d1"
+            rows = []
+        "#;
+
+        let mut w = Wayline::default();
+        w.load_all(GEMS);
+        assert_eq!(w.tables.len(), 2);
+        let _ = w.dispatch(command::parse_command("use Gems"));
+        assert_eq!(w.current_table.as_deref(), Some("Gems"));
+        let _ = w.dispatch(command::parse_command("use gems"));
+        assert_eq!(w.current_table.as_deref(), Some("GEMS"));
+
+        let mut w = Wayline::default();
+        w.load_all(&format!("[matching]\ncase_sensitive = true\n{}", GEMS));
+        let _ = w.dispatch(command::parse_command("use gems"));
+        assert_eq!(w.current_table, None);
+        assert_eq!(last_line(&w), "Table 'gems' not found.");
+        let _ = w.dispatch(command::parse_command("use GEMS"));
+        assert_eq!(w.current_table.as_deref(), Some("GEMS"));
+    }
+
+    #[test]
+    fn test_use_by_list_index() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+
+        let _ = w.dispatch(command::parse_command("use 1"));
+        assert_eq!(last_line(&w), "No table #1 in the last listing (0 listed).");
+
+        w.on_list_command(Some("all".to_string()));
+        assert_eq!(last_lines(&w, 2), ["1. 1d20 (1d1, 2 entries)", "2. Monsters (1d1, 1 entry)"]);
+
+        let _ = w.dispatch(command::parse_command("use 2"));
+        assert_eq!(w.current_table.as_deref(), Some("Monsters"));
+
+        let _ = w.dispatch(command::parse_command("use 3"));
+        assert_eq!(last_line(&w), "No table #3 in the last listing (2 listed).");
+        let _ = w.dispatch(command::parse_command("use 0"));
+        assert_eq!(last_line(&w), "No table #0 in the last listing (2 listed).");
+        assert_eq!(w.current_table.as_deref(), Some("Monsters"));
+    }
+
+    #[test]
+    fn test_roll_until() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "use 1d20");
+
+        submit(&mut w, "rolluntil goblin 5");
+        assert_eq!(last_line(&w), "1d20: no 'goblin' in 5 attempt(s).");
+        submit(&mut w, "rolluntil not dice");
+        assert_eq!(last_line(&w), "1d20 -> (1): rolled: Not Dice after 1 attempt(s)");
+        submit(&mut w, "rolluntil dragon");
+        assert_eq!(last_line(&w), "No entry named 'dragon' in '1d20'.");
+    }
+
+    #[test]
+    fn test_reload_reports_failed_tables() {
+        let mut w = Wayline::default();
+        w.reload_tables(TEST_TABLES);
+        assert_eq!(last_line(&w), r#"Reloaded tables from tables.toml: ["1d20", "Monsters"]."#);
+
+        let broken = format!("{}\n[[table]]\nname = \"Broken\"\nrows = []\n", TEST_TABLES);
+        w.reload_tables(&broken);
+        assert_eq!(
+            last_lines(&w, 2),
+            [
+                "Reloaded 2 tables from tables.toml, 1 failed:",
+                "  table #3 in tables.toml: missing field `dice`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_table() {
+        let mut w = Wayline::default();
+        w.load_all(&format!("default = \"monsters\"\n{}", TEST_TABLES));
+        w.select_default_table();
+        assert_eq!(w.current_table.as_deref(), Some("Monsters"));
+
+        let mut w = Wayline::default();
+        w.load_all(&format!("default = \"dragons\"\n{}", TEST_TABLES));
+        w.select_default_table();
+        assert_eq!(last_lines(&w, 2)[0], "Default table 'dragons' not found.");
+        assert_eq!(w.current_table.as_deref(), Some("1d20"));
+    }
+
+    #[test]
+    fn test_startup_table_is_alphabetical_first() {
+        let mut w = Wayline::default();
+        for name in ["Zombies", "Apes", "Bandits"] {
+            w.load_all(&format!("[[table]]\nname = \"{}\"\ndice = \"1d1\"\nrows = []", name));
+        }
+        assert_eq!(w.tables.len(), 3);
+        w.select_default_table();
+        assert_eq!(w.current_table.as_deref(), Some("Apes"));
+        assert_eq!(last_line(&w), "Current table set to 'Apes'.");
+    }
+
+    #[test]
+    fn test_gen_follows_subtables() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Encounters"
+            dice = "1d1"
+            [[table.rows]]
+            name = "Goblin Patrol"
+            numbers = [1]
+            subtable = "Goblin Gear"
+
+            [[table]]
+            name = "Goblin Gear"
+            dice = "1d1"
+            [[table.rows]]
+            name = "Rusty Sword"
+            numbers = [1]
+            subtable = "Trinkets"
+            "#,
+        );
+
+        submit(&mut w, "gen encounters");
+        assert_eq!(
+            last_lines(&w, 3),
+            [
+                "Encounters -> (1): Goblin Patrol",
+                "  Goblin Gear -> (1): Rusty Sword",
+                "    Table 'Trinkets' not found.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gen_stops_at_max_table_depth() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            max_table_depth = 3
+
+            [[table]]
+            name = "Ping"
+            dice = "1d1"
+            [[table.rows]]
+            name = "To Pong"
+            numbers = [1]
+            subtable = "Pong"
+
+            [[table]]
+            name = "Pong"
+            dice = "1d1"
+            [[table.rows]]
+            name = "To Ping"
+            numbers = [1]
+            subtable = "Ping"
+            "#,
+        );
+
+        submit(&mut w, "gen ping");
+        assert_eq!(
+            last_lines(&w, 4),
+            [
+                "Ping -> (1): To Pong",
+                "  Pong -> (1): To Ping",
+                "    Ping -> (1): To Pong",
+                "      Recursion limit reached: 'Pong' would be 4 tables deep (max 3).",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undo_redo_time_and_tables() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+
+        submit(&mut w, "use Monsters");
+        submit(&mut w, "add 30");
+        submit(&mut w, "use 1d20");
+        submit(&mut w, "roll"); // Not undoable
+        submit(&mut w, "turn");
+        assert_eq!(w.current_time_minutes(), 40);
+        assert_eq!(w.turns, 1);
+
+        submit(&mut w, "undo");
+        assert_eq!(w.current_time_minutes(), 30);
+        assert_eq!(w.turns, 0);
+        submit(&mut w, "redo");
+        assert_eq!(w.turns, 1);
+        submit(&mut w, "undo");
+        submit(&mut w, "undo");
+        assert_eq!(w.current_table.as_deref(), Some("Monsters"));
+        submit(&mut w, "undo");
+        assert_eq!(w.current_time_minutes(), 0);
+        submit(&mut w, "undo");
+        assert_eq!(w.current_table, None);
+        submit(&mut w, "undo");
+        assert_eq!(last_line(&w), "Nothing to undo.");
+
+        submit(&mut w, "redo");
+        submit(&mut w, "redo");
+        assert_eq!(w.current_table.as_deref(), Some("Monsters"));
+        assert_eq!(w.current_time_minutes(), 30);
+
+        // A new change discards what was left to redo.
+        submit(&mut w, "sub 10");
+        submit(&mut w, "redo");
+        assert_eq!(last_line(&w), "Nothing to redo.");
+        assert_eq!(w.current_time_minutes(), 20);
+
+        // Undoing back to a table that's gone leaves the current one alone.
+        w.history = History::default();
+        submit(&mut w, "use 1d20");
+        w.tables.remove("Monsters");
+        submit(&mut w, "undo");
+        assert_eq!(
+            last_line(&w),
+            "Table 'Monsters' no longer exists, so the current table is unchanged."
+        );
+        assert_eq!(w.current_table.as_deref(), Some("1d20"));
+    }
+
+    #[test]
+    fn test_back_to_previous_table() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+
+        submit(&mut w, "back");
+        assert_eq!(last_line(&w), "No previous table.");
+
+        submit(&mut w, "use Monsters");
+        submit(&mut w, "use 1d20");
+        submit(&mut w, "back");
+        assert_eq!(w.current_table.as_deref(), Some("Monsters"));
+        submit(&mut w, "back");
+        assert_eq!(w.current_table.as_deref(), Some("1d20"));
+    }
+
+    #[test]
+    fn test_pasted_block_runs_each_line() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "use monsters\n\n# a comment\n  add 15\nroll\n");
+
+        let lines: Vec<&str> = w.scrollback.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(
+            lines,
+            [
+                "> use monsters",
+                "Switched to table 'Monsters'.",
+                "> add 15",
+                "Added 15 minutes. New time: 00:15 (night)",
+                "> roll",
+                "Monsters -> (1): rolled: Goblin",
+            ]
+        );
+        assert!(w.input.is_empty());
+    }
+
+    #[test]
+    fn test_startup_commands() {
+        let mut w = Wayline::default();
+        w.load_all(&format!(
+            "[startup]\ncommands = [\"use monsters\", \"# comment\", \"settime 08:00\"]\n{}",
+            TEST_TABLES
+        ));
+        let _ = w.run_startup_commands();
+
+        assert_eq!(w.current_table.as_deref(), Some("Monsters"));
+        assert!(!w.scrollback.iter().any(|line| line.text.contains("# comment")));
+        assert_eq!(last_line(&w), "Startup command failed: settime 08:00");
+    }
+
+    #[test]
+    fn test_blank_input_does_nothing() {
+        let mut w = Wayline::default();
+        submit(&mut w, "   ");
+        submit(&mut w, "");
+        assert!(w.scrollback.is_empty());
+        assert!(w.input_history.is_empty());
+    }
+
+    #[test]
+    fn test_recall_input() {
+        let mut w = Wayline::default();
+        let press = |w: &mut Wayline, key| {
+            let _ = w.on_key_pressed(
+                &keyboard::Key::Named(key),
+                keyboard::Modifiers::default(),
+            );
+        };
+        press(&mut w, keyboard::key::Named::ArrowUp);
+        assert_eq!(w.input, "");
+
+        submit(&mut w, "time");
+        submit(&mut w, "add 10");
+        press(&mut w, keyboard::key::Named::ArrowUp);
+        assert_eq!(w.input, "add 10");
+        press(&mut w, keyboard::key::Named::ArrowUp);
+        press(&mut w, keyboard::key::Named::ArrowUp);
+        assert_eq!(w.input, "time");
+        press(&mut w, keyboard::key::Named::ArrowDown);
+        assert_eq!(w.input, "add 10");
+        press(&mut w, keyboard::key::Named::ArrowDown);
+        assert_eq!(w.input, "");
+
+        // Typing starts over from the newest.
+        press(&mut w, keyboard::key::Named::ArrowUp);
+        press(&mut w, keyboard::key::Named::ArrowUp);
+        let _ = w.update(Message::ContentChanged("ro".to_string()));
+        press(&mut w, keyboard::key::Named::ArrowUp);
+        assert_eq!(w.input, "add 10");
+    }
+
+    #[test]
+    fn test_comment_input_is_not_run() {
+        let mut w = Wayline::default();
+        submit(&mut w, "# the party rests");
+        assert_eq!(last_lines(&w, 1), ["> # the party rests"]);
+        assert_eq!(w.scrollback.len(), 1);
+    }
+
+    #[test]
+    fn test_running_total() {
+        let mut w = Wayline::default();
+        let _ = w.dispatch(command::parse_command("+1d1"));
+        let _ = w.dispatch(command::parse_command("+1d1"));
+        assert_eq!(w.total, 2);
+        assert_eq!(last_line(&w), "Rolled 1d1: 1, subtotal: 2");
+
+        let _ = w.dispatch(command::parse_command("total reset"));
+        assert_eq!(w.total, 0);
+        assert_eq!(last_line(&w), "subtotal: 0");
+    }
+
+    #[test]
+    fn test_last_result() {
+        let mut w = Wayline::default();
+        let _ = w.dispatch(Command::Last);
+        assert_eq!(last_line(&w), "No results yet.");
+
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "roll monsters");
+        submit(&mut w, "bogus");
+        submit(&mut w, "last");
+        assert_eq!(last_line(&w), "Monsters -> (1): rolled: Goblin");
+    }
+
+    #[test]
+    fn test_set_day_command() {
+        let mut w = Wayline::default();
+        submit(&mut w, "add 390");
+        submit(&mut w, "day 5");
+        assert_eq!(last_line(&w), "Now day 5, 06:30 (dawn).");
+        assert_eq!(w.current_time_minutes(), 4 * api::MINUTES_PER_DAY + 390);
+        submit(&mut w, "time");
+        assert_eq!(last_lines(&w, 2)[0], "Current in-game time: day 5, 06:30 (dawn)");
+
+        submit(&mut w, "day +2");
+        assert_eq!(last_line(&w), "Now day 7, 06:30 (dawn).");
+        submit(&mut w, "day -6");
+        assert_eq!(last_line(&w), "Now day 1, 06:30 (dawn).");
+
+        submit(&mut w, "day -1");
+        assert_eq!(last_line(&w), "There's no day before day 1.");
+        submit(&mut w, "day 0");
+        assert_eq!(last_line(&w), "There's no day before day 1.");
+        submit(&mut w, "day 9999999");
+        assert_eq!(last_line(&w), "Day 9999999 is too far off.");
+        assert_eq!(w.current_time_minutes(), 390);
+
+        submit(&mut w, "undo");
+        assert_eq!(w.current_time_minutes(), 6 * api::MINUTES_PER_DAY + 390);
+    }
+
+    #[test]
+    fn test_goto_dawn_crosses_midnight() {
+        let mut w = Wayline::default();
+        let _ = w.dispatch(Command::Add(8 * 60));
+        let _ = w.dispatch(command::parse_command("goto dawn"));
+        assert_eq!(w.current_time_minutes(), 24 * 60 + 6 * 60);
+        assert_eq!(last_line(&w), "Advanced 1320 minutes to dawn (06:00).");
+
+        w.load_all("[time.anchors]\ndawn = \"05:00\"");
+        let _ = w.dispatch(command::parse_command("goto dawn"));
+        assert_eq!(last_line(&w), "Advanced 1380 minutes to dawn (05:00).");
+    }
+
+    #[test]
+    fn test_add_huge_minutes() {
+        let mut w = Wayline::default();
+        submit(&mut w, "add 4294967295");
+        assert_eq!(w.current_time_minutes(), command::MAX_MINUTES);
+        w.add_minutes(u32::MAX);
+        assert_eq!(w.current_time_minutes(), u32::MAX);
+        w.on_turn_command();
+        assert_eq!(w.current_time_minutes(), u32::MAX);
+    }
+
+    #[test]
+    fn test_clocks() {
+        let mut w = Wayline::default();
+        let _ = w.dispatch(Command::Add(30));
+        let _ = w.dispatch(command::parse_command("clock new east"));
+        let _ = w.dispatch(command::parse_command("clock use east"));
+        let _ = w.dispatch(Command::Add(90));
+        let _ = w.dispatch(Command::Sub(15));
+        assert_eq!(w.current_time_minutes(), 75);
+
+        w.on_time_command();
+        assert_eq!(last_lines(&w, 2)[0], "Current in-game time (east): day 1, 01:15 (night)");
+
+        let _ = w.dispatch(command::parse_command("clock use default"));
+        assert_eq!(w.current_time_minutes(), 30);
+        w.on_time_command();
+        assert_eq!(last_lines(&w, 2)[0], "Current in-game time: day 1, 00:30 (night)");
+
+        let _ = w.dispatch(command::parse_command("clock use west"));
+        assert_eq!(last_line(&w), "Clock 'west' not found.");
+        assert_eq!(w.active_clock, None);
+    }
+
+    #[test]
+    fn test_dice_min_clamps() {
+        let mut w = Wayline::default();
+        let _ = w.dispatch(command::parse_command("dice 2d1 min 7"));
+        assert_eq!(last_line(&w), "Rolled 2d1 min 7: 7 (raw 2)");
+    }
+
+    #[test]
+    fn test_dice_loaded() {
+        let mut w = Wayline::default();
+        submit(&mut w, "dice w[2:0]2d2");
+        assert_eq!(last_line(&w), "Rolled w[2:0]2d2: 2");
+        submit(&mut w, "dice w[3:1]d2");
+        assert_eq!(
+            last_line(&w),
+            "Invalid dice: 'w[3:1]d2' has bad face weights: there's no face 3 on a d2"
+        );
+    }
+
+    #[test]
+    fn test_roll_shows_entry_details() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d1"
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1]
+            xp = 50
+            cr = "1/4"
+            "#,
+        );
+        submit(&mut w, "roll monsters");
+        assert_eq!(last_line(&w), "Monsters -> (1): rolled: Goblin (cr: 1/4, xp: 50)");
+    }
+
+    #[test]
+    fn test_roll_shows_quantity() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d1"
+            quantity = "2d1"
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1]
+            [[table]]
+            name = "Lairs"
+            dice = "1d1"
+            quantity = "2d1"
+            [[table.rows]]
+            name = "Dragon"
+            numbers = [1]
+            quantity = "1"
+            [[table]]
+            name = "Hordes"
+            dice = "1d1"
+            [[table.rows]]
+            name = "Orc"
+            numbers = [1]
+            quantity = "many"
+            "#,
+        );
+        submit(&mut w, "roll monsters");
+        assert_eq!(last_line(&w), "Monsters -> (1): rolled: Goblin ×2 (2d1)");
+        submit(&mut w, "roll lairs");
+        assert_eq!(last_line(&w), "Lairs -> (1): rolled: Dragon ×1");
+        submit(&mut w, "roll hordes");
+        assert_eq!(
+            last_lines(&w, 2),
+            [
+                "Hordes -> (1): rolled: Orc",
+                "Warning: table 'Hordes': can't roll quantity many for 'Orc'."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_roll_rerolls_once() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d1"
+            reroll_on = [1, 2]
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1]
+            [[table.rows]]
+            name = "Orc"
+            numbers = [2]
+            "#,
+        );
+        submit(&mut w, "roll monsters");
+        assert_eq!(last_line(&w), "Monsters -> (1 rerolled, 1): rolled: Goblin");
+        submit(&mut w, "roll monsters with 2d1");
+        assert_eq!(last_line(&w), "Monsters -> (1+1=2 rerolled, 1+1=2): rolled: Orc");
+        submit(&mut w, "roll monsters with 3d1");
+        assert_eq!(last_line(&w), "Monsters -> (1+1+1=3): no matching entry found.");
+    }
+
+    #[test]
+    fn test_what_if_command() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Reaction"
+            dice = "2d6"
+            [[table.rows]]
+            name = "Hostile"
+            numbers = [2, 3]
+            [[table.rows]]
+            name = "Unsure"
+            min = 4
+            max = 10
+            [[table.rows]]
+            name = "Friendly"
+            numbers = [11, 12]
+            [[table.rows]]
+            name = "Allied"
+            min = 13
+            "#,
+        );
+        submit(&mut w, "use reaction");
+        submit(&mut w, "whatif +2");
+        assert_eq!(
+            last_lines(&w, 3),
+            [
+                "Reaction (2d6) at +2: rolls 4-14 instead of 2-12.",
+                "  Now reachable: Allied [13+]",
+                "  No longer reachable: Hostile [2, 3]",
+            ]
+        );
+
+        submit(&mut w, "whatif reaction -3");
+        assert_eq!(
+            last_lines(&w, 3),
+            [
+                "Reaction (2d6) at -3: rolls 0-9 instead of 2-12.",
+                "  No longer reachable: Friendly [11, 12]",
+                "  No entry covers 0-1.",
+            ]
+        );
+
+        // Huge dice only have their new results checked.
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Huge"
+            dice = "1d4000000000"
+            fallback = "Nothing"
+            [[table.rows]]
+            name = "Anything"
+            max = 4000000000
+            "#,
+        );
+        submit(&mut w, "whatif huge +1");
+        assert_eq!(
+            last_lines(&w, 3),
+            [
+                "Huge (1d4000000000) at +1: rolls 2-4000000001 instead of 1-4000000000.",
+                "  The same entries stay reachable.",
+                "  No entry covers 4000000001, giving 'Nothing'.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_odds_command() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d8"
+            [[table.rows]]
+            name = "Orc"
+            numbers = [1, 2]
+            [[table.rows]]
+            name = "Goblin Ambush"
+            min = 3
+            max = 6
+            [[table.rows]]
+            name = "Dragon"
+            numbers = [9]
+            [[table.rows]]
+            name = "Ogre"
+            numbers = [7]
+            [[table.rows]]
+            name = "Troll"
+            numbers = [8]
+            enabled = false
+            "#,
+        );
+        submit(&mut w, "odds monsters");
+        assert_eq!(
+            last_lines(&w, 6),
+            [
+                "Odds on Monsters (1d8), most likely first:",
+                "  Goblin Ambush [3-6]  50.0% ####################",
+                "  Orc [1, 2]           25.0% ##########",
+                "  Ogre [7]             12.5% #####",
+                "  Dragon [9]            0.0% (never comes up)",
+                "  Troll [8]             0.0% (disabled)",
+            ]
+        );
+
+        submit(&mut w, "odds dragons");
+        assert_eq!(last_line(&w), "Table 'dragons' not found.");
+    }
+
+    #[test]
+    fn test_matrix_command() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Terrain"
+            dice = "1d2"
+            [[table.rows]]
+            name = "Forest"
+            numbers = [1]
+            [[table.rows]]
+            name = "Hills"
+            numbers = [2]
+
+            [[table]]
+            name = "Creatures"
+            dice = "1d2"
+            [[table.rows]]
+            name = "Wolves"
+            numbers = [1]
+            "#,
+        );
+        let seeded = || rand::rngs::StdRng::seed_from_u64(3);
+        let mut rng = seeded();
+        let terrain = w.roll_table(&mut rng, Some("terrain".to_string()), None, &[]).unwrap();
+        let creatures = w.roll_table(&mut rng, Some("creatures".to_string()), None, &[]).unwrap();
+
+        w.on_matrix_command(&mut seeded(), "terrain", "creatures");
+        assert_eq!(
+            last_line(&w),
+            format!(
+                "Terrain + Creatures -> ({}, {}): rolled: {} + {}",
+                terrain.roll,
+                creatures.roll,
+                terrain.entry.unwrap(),
+                creatures.entry.as_deref().unwrap_or("no matching entry")
+            )
+        );
+        assert_eq!(w.recent_rolls.len(), 2);
+
+        submit(&mut w, "matrix terrain dragons");
+        assert_eq!(last_line(&w), "Table 'dragons' not found.");
+        submit(&mut w, "matrix dragons terrain");
+        assert_eq!(last_line(&w), "Table 'dragons' not found.");
+        assert_eq!(w.recent_rolls.len(), 2);
+    }
+
+    #[test]
+    fn test_roll_json_command() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "roll monsters --json");
+        let json: serde_json::Value = serde_json::from_str(last_line(&w)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "table": "Monsters",
+                "dice": "1d1",
+                "roll": 1,
+                "breakdown": "1",
+                "entry": "Goblin",
+                "result": "Goblin",
+                "warnings": [],
+            })
+        );
+
+        submit(&mut w, "roll-json monsters :night");
+        let json: serde_json::Value = serde_json::from_str(last_line(&w)).unwrap();
+        assert_eq!(json["warnings"], serde_json::json!(["no modifier 'night', rolled without it"]));
+        assert_eq!(w.recent_rolls.len(), 2);
+
+        submit(&mut w, "roll 3 monsters --json");
+        let json: serde_json::Value = serde_json::from_str(last_line(&w)).unwrap();
+        let rolls = json.as_array().unwrap();
+        assert_eq!(rolls.len(), 3);
+        assert!(rolls.iter().all(|roll| roll["entry"] == "Goblin"));
+        assert_eq!(w.recent_rolls.len(), 5);
+
+        submit(&mut w, "roll-json dragons");
+        assert_eq!(last_line(&w), "Table 'dragons' not found.");
+    }
+
+    #[test]
+    fn test_roll_shows_seen_details() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Encounters"
+            dice = "1d1"
+            [[table.rows]]
+            name = "Goblin Scouts"
+            numbers = [1]
+            see = "Goblin Ambush"
+            xp = 25
+            [[table.rows]]
+            name = "Goblin Ambush"
+            see = "Goblin Scouts"
+            cr = "1/4"
+            xp = 50
+            [[table.rows]]
+            name = "Orc"
+            see = "Ogre"
+            "#,
+        );
+        assert_eq!(
+            last_line(&w),
+            "Warning: table 'Encounters': entry 'Orc' sees 'Ogre', which isn't in the table."
+        );
+
+        submit(&mut w, "roll encounters");
+        assert_eq!(last_line(&w), "Encounters -> (1): rolled: Goblin Scouts (cr: 1/4, xp: 25)");
+    }
+
+    #[test]
+    fn test_force_command() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d6"
+            columns = ["lair"]
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1, 2, 3]
+            quantity = "4"
+            columns = { lair = "cave" }
+            [[table.rows]]
+            name = "Orc"
+            numbers = [4, 5, 6]
+            "#,
+        );
+        submit(&mut w, "roll 1d1");
+        let roll_log = w.roll_log.clone();
+        let recent_rolls = w.recent_rolls.len();
+
+        submit(&mut w, "force monsters 1");
+        assert_eq!(
+            last_line(&w),
+            "Monsters -> (forced row 1): rolled: Goblin ×4 [lair: cave]"
+        );
+        submit(&mut w, "force Monsters 2");
+        assert_eq!(last_line(&w), "Monsters -> (forced row 2): rolled: Orc [lair: -]");
+        assert_eq!(w.recent_rolls.len(), recent_rolls);
+        assert_eq!(w.last_result.as_deref(), Some("Rolled 1d1: 1"));
+        assert_eq!(w.roll_log, roll_log);
+        assert!(!w.roll_log.is_empty());
+
+        submit(&mut w, "force monsters 3");
+        assert_eq!(last_line(&w), "Table 'Monsters' has no row 3; it has 2 rows.");
+        submit(&mut w, "force monsters 0");
+        assert_eq!(last_line(&w), "Table 'Monsters' has no row 0; it has 2 rows.");
+        submit(&mut w, "force dragons 1");
+        assert_eq!(last_line(&w), "Table 'dragons' not found.");
+    }
+
+    #[test]
+    fn test_add_rolled_minutes() {
+        let mut w = Wayline::default();
+        let seeded = || rand::rngs::StdRng::seed_from_u64(5);
+        let expected = api::roll_with(&mut seeded(), "1d10").unwrap();
+        w.add_rolled_minutes(&mut seeded(), "1d10");
+        assert_eq!(w.current_time_minutes(), expected);
+        assert_eq!(
+            last_lines(&w, 2),
+            [
+                format!("Rolled 1d10 for time: {}", expected),
+                format!("Added {} minutes. New time: {}", expected, w.format_time(expected)),
+            ]
+        );
+
+        submit(&mut w, "add 2d1+3");
+        assert_eq!(w.current_time_minutes(), expected + 5);
+        submit(&mut w, "add 1d1-4");
+        let time = w.format_time(expected + 5);
+        assert_eq!(last_line(&w), format!("Added 0 minutes. New time: {}", time));
+        submit(&mut w, "add 10001d6");
+        assert_eq!(last_line(&w), "Can't roll 10001d6 for the time taken.");
+    }
+
+    #[test]
+    fn test_summary_command() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [aliases]
+            n = "time"
+            m = "use monsters"
+
+            [[table]]
+            name = "Monsters"
+            dice = "1d1"
+            [table.aliases]
+            n = "roll"
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1]
+
+            [[table]]
+            name = "Hexes"
+            dice = "1d1"
+            [[table.rows]]
+            name = "Forest"
+            numbers = [1]
+            "#,
+        );
+        submit(&mut w, "use monsters");
+        submit(&mut w, "add 1830");
+        submit(&mut w, "summary");
+        assert_eq!(
+            last_lines(&w, 5),
+            [
+                "Tables loaded: 2",
+                "Current table: Monsters",
+                "Time: day 2, 06:30 (dawn) on the default clock",
+                "Aliases: m = use monsters, n = time",
+                "Monsters aliases: n = roll",
+            ]
+        );
+
+        let mut w = Wayline::default();
+        submit(&mut w, "summary");
+        assert_eq!(last_line(&w), "Aliases: none");
+    }
+
+    #[test]
+    fn test_aliases_follow_current_table() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [aliases]
+            m = "use monsters"
+            n = "time"
+
+            [[table]]
+            name = "Monsters"
+            dice = "1d1"
+            [table.aliases]
+            n = "roll"
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1]
+
+            [[table]]
+            name = "Hexes"
+            dice = "1d1"
+            [[table.rows]]
+            name = "Forest"
+            numbers = [1]
+            "#,
+        );
+        submit(&mut w, "use hexes");
+        submit(&mut w, "n");
+        assert_eq!(last_line(&w), "Turns: 0, watches: 0");
+
+        submit(&mut w, "m");
+        assert_eq!(w.current_table.as_deref(), Some("Monsters"));
+        submit(&mut w, "N");
+        assert_eq!(last_line(&w), "Monsters -> (1): rolled: Goblin");
+        submit(&mut w, "n hexes");
+        assert_eq!(last_line(&w), "Hexes -> (1): rolled: Forest");
+
+        submit(&mut w, "use hexes");
+        submit(&mut w, "n");
+        assert_eq!(last_line(&w), "Turns: 0, watches: 0");
+    }
+
+    #[test]
+    fn test_reset_command() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "use monsters");
+        submit(&mut w, "clock new west");
+        submit(&mut w, "add 90");
+        submit(&mut w, "clock use west");
+        submit(&mut w, "watch");
+        submit(&mut w, "+1d1");
+        submit(&mut w, "roll");
+
+        submit(&mut w, "reset");
+        assert_eq!(last_line(&w), "Current table set to '1d20'.");
+        assert!(w.clocks.values().all(|minutes| *minutes == 0));
+        assert!(w.clocks.contains_key("west"));
+        assert_eq!(w.active_clock, None);
+        assert_eq!((w.turns, w.total), (0, 0));
+        assert!(w.recent_rolls.is_empty());
+        assert_eq!(w.status_line(), "Day 1, 00:00 | 1d20");
+        assert_eq!(w.tables.len(), 2);
+        submit(&mut w, "undo");
+        assert_eq!(last_line(&w), "Nothing to undo.");
+    }
+
+    #[test]
+    fn test_status_line() {
+        let mut w = Wayline::default();
+        assert_eq!(w.status_line(), "Day 1, 00:00 | no table");
+
+        w.load_all(TEST_TABLES);
+        w.current_table = Some("Monsters".to_string());
+        w.set_current_time_minutes(api::MINUTES_PER_DAY + 6 * 60 + 30);
+        assert_eq!(w.status_line(), "Day 2, 06:30 | Monsters");
+    }
+
+    #[test]
+    fn test_roll_table_outcome() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d6"
+            modifiers = { night = 1 }
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1, 2, 3]
+            [[table.rows]]
+            name = "Orc"
+            numbers = [4, 5, 6, 7]
+            "#,
+        );
+        let roll = |seed, modifiers: &[String]| {
+            let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+            w.roll_table(&mut rng, Some("monsters".to_string()), None, modifiers).unwrap()
+        };
+
+        let outcome = roll(7, &[]);
+        assert_eq!(outcome, roll(7, &[]));
+        assert_eq!(outcome.table, "Monsters");
+        assert_eq!(outcome.dice, "1d6");
+        let expected = if outcome.roll <= 3 { "Goblin" } else { "Orc" };
+        assert_eq!(outcome.entry.as_deref(), Some(expected));
+        assert_eq!(outcome.breakdown, outcome.roll.to_string());
+
+        let night = roll(7, &["night".to_string()]);
+        assert_eq!(night.roll, outcome.roll + 1);
+        assert!(night.warnings.is_empty());
+        let unknown = roll(7, &["rain".to_string()]);
+        assert_eq!(unknown.warnings, ["no modifier 'rain', rolled without it"]);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        assert_eq!(
+            w.roll_table(&mut rng, Some("dragons".to_string()), None, &[]),
+            Err("Table 'dragons' not found.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_roll_table_column() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Hexes"
+            dice = "1d1"
+            columns = ["terrain", "weather"]
+            [[table.rows]]
+            name = "Wet Woods"
+            numbers = [1]
+            columns = { terrain = "Forest", weather = "Rain" }
+            "#,
+        );
+
+        submit(&mut w, "roll hexes.Weather");
+        assert_eq!(last_line(&w), "Hexes.weather -> (1): rolled: Rain");
+        submit(&mut w, "roll hexes");
+        assert_eq!(
+            last_line(&w),
+            "Hexes -> (1): rolled: Wet Woods [terrain: Forest, weather: Rain]"
+        );
+        submit(&mut w, "roll hexes.wind");
+        assert_eq!(last_line(&w), "Table 'Hexes' has no column 'wind'.");
+    }
+
+    #[test]
+    fn test_roll_with_modifier() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d1"
+            [table.modifiers]
+            night = 1
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1]
+            [[table.rows]]
+            name = "Ghoul"
+            numbers = [2]
+            "#,
+        );
+        submit(&mut w, "roll monsters :night");
+        assert_eq!(last_line(&w), "Monsters -> (1 +1 night = 2): rolled: Ghoul");
+
+        submit(&mut w, "roll monsters :rain");
+        assert_eq!(
+            last_lines(&w, 2),
+            [
+                "Monsters -> (1): rolled: Goblin",
+                "Warning: table 'Monsters': no modifier 'rain', rolled without it.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_roll_uses_fallback() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d1"
+            fallback = "Nothing"
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1]
+            "#,
+        );
+        w.on_roll_command(Some("Monsters".to_string()), Some("2d1".to_string()), Vec::new());
+        assert_eq!(last_line(&w), "Monsters -> (1+1=2): rolled: Nothing");
+        w.on_roll_command(Some("1d20".to_string()), Some("2d1".to_string()), Vec::new());
+        assert_eq!(last_line(&w), "1d20 -> (1+1=2): rolled: Goblin");
+    }
+
+    #[test]
+    fn test_disabled_entry_is_never_rolled() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d2"
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1]
+            enabled = false
+            [[table.rows]]
+            name = "Orc"
+            numbers = [1, 2]
+            "#,
+        );
+        submit(&mut w, "sample 200 monsters");
+        assert_eq!(last_lines(&w, 2), ["Monsters, 200 rolls:", "- Orc: 200 (100.0%)"]);
+        submit(&mut w, "list monsters");
+        assert_eq!(last_lines(&w, 2), ["- Goblin: [1] (disabled)", "- Orc: [1, 2]"]);
+    }
+
+    #[test]
+    fn test_roll_many_unique() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d3"
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1]
+            [[table.rows]]
+            name = "Orc"
+            numbers = [2]
+            [[table.rows]]
+            name = "Troll"
+            numbers = [3]
+            "#,
+        );
+        submit(&mut w, "roll 5 monsters unique");
+        let lines = last_lines(&w, 4);
+        assert_eq!(lines[3], "Only 3 different results on 'Monsters' after 100 rerolls.");
+        let mut names: Vec<&str> =
+            lines[..3].iter().map(|line| line.rsplit(' ').next().unwrap()).collect();
+        names.sort();
+        assert_eq!(names, ["Goblin", "Orc", "Troll"]);
+
+        submit(&mut w, "roll 4 monsters");
+        assert!(last_lines(&w, 4).iter().all(|line| line.starts_with("Monsters -> (")));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |list: &[&str]| parse_args(list.iter().map(|arg| arg.to_string()));
+        assert_eq!(args(&[]), Ok(Args::default()));
+        assert_eq!(
+            args(&["--no-config", "--log", "events.jsonl", "dice", "2d6"]),
+            Ok(Args {
+                log: Some("events.jsonl".to_string()),
+                log_file: None,
+                no_config: true,
+                quiet: false,
+                tables_dir: None,
+                config: None,
+                check: None,
+                command: vec!["dice".to_string(), "2d6".to_string()],
+            })
+        );
+        assert_eq!(
+            args(&["roll", "--no-config"]).map(|args| args.no_config),
+            Ok(false)
+        );
+        assert_eq!(args(&["--log"]), Err("--log needs a file path".to_string()));
+        assert_eq!(
+            args(&["--tables-dir", "tables"]).map(|args| args.tables_dir),
+            Ok(Some(PathBuf::from("tables")))
+        );
+        assert_eq!(
+            args(&["--config", "campaign.toml"]).map(|args| args.config),
+            Ok(Some(PathBuf::from("campaign.toml")))
+        );
+        assert_eq!(
+            args(&["--check", "tables.toml"]).map(|args| args.check),
+            Ok(Some(PathBuf::from("tables.toml")))
+        );
+    }
+
+    #[test]
+    fn test_check_tables() {
+        let path = Path::new("tables.toml");
+        assert!(check_tables(path, TEST_TABLES).is_empty());
+
+        let source = r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d6"
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1, 2]
+            [[table.rows]]
+            name = "Dragon"
+            numbers = [7]
+
+            [[table]]
+            name = "Broken"
+        "#;
+        assert_eq!(
+            check_tables(path, source),
+            [
+                "table #2 in tables.toml: missing field `rows`",
+                "tables.toml: table 'Monsters': entry 'Dragon' can never be rolled on 1d6",
+                "tables.toml: table 'Monsters': no entry covers 3-6",
+            ]
+        );
+        assert_eq!(check_tables(Path::new("tables.json"), "{").len(), 1);
+    }
+
+    #[test]
+    fn test_resolve_config_path() {
+        let user_dir = Some(PathBuf::from("/home/me/.config/wayline"));
+        let user = PathBuf::from("/home/me/.config/wayline/tables.toml");
+        let local = PathBuf::from("tables.toml");
+        let existing = |paths: &'static [&'static str]| {
+            move |path: &Path| paths.iter().any(|existing| Path::new(existing) == path)
+        };
+
+        let explicit = Some(PathBuf::from("campaign.toml"));
+        let both = existing(&["tables.toml", "/home/me/.config/wayline/tables.toml"]);
+        let chosen = resolve_config_path(explicit, user_dir.clone(), both);
+        assert_eq!(chosen, PathBuf::from("campaign.toml"));
+        assert_eq!(resolve_config_path(None, user_dir.clone(), both), local);
+        let user_only = existing(&["/home/me/.config/wayline/tables.toml"]);
+        assert_eq!(resolve_config_path(None, user_dir.clone(), user_only), user);
+        assert_eq!(resolve_config_path(None, user_dir, existing(&[])), local);
+        assert_eq!(resolve_config_path(None, None, existing(&[])), local);
+    }
+
+    #[test]
+    fn test_no_config_starts_without_tables() {
+        let mut w = Wayline { no_config: true, ..Wayline::default() };
+        let _ = w.update(Message::WindowOpened);
+        assert_eq!(last_line(&w), "Started with no tables loaded.");
+        assert!(w.tables.is_empty());
+    }
+
+    #[test]
+    fn test_tables_dir() {
+        let dir = std::env::temp_dir().join(format!("wayline-tables-dir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("monsters.toml"),
+            "[[table]]\nname = \"Monsters\"\ndice = \"1d1\"\nrows = []",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("loot.JSON"),
+            r#"{"table": [{"name": "Loot", "dice": "1d1", "rows": []}]}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a table").unwrap();
+
+        let mut w = Wayline { tables_dir: Some(dir.clone()), ..Wayline::default() };
+        w.load_and_report_tables_dir();
+        assert_eq!(
+            last_lines(&w, 2),
+            [
+                format!("Loaded 1 tables from {}.", dir.join("loot.JSON").display()),
+                format!("Loaded 1 tables from {}.", dir.join("monsters.toml").display()),
+            ]
+        );
+        assert_eq!(w.sorted_table_names(), ["Loot", "Monsters"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(&dir).unwrap();
+        w.load_and_report_tables_dir();
+        assert_eq!(last_line(&w), format!("No .toml or .json files in {}.", dir.display()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        w.load_and_report_tables_dir();
+        assert_eq!(last_line(&w), format!("Can't read tables directory {}.", dir.display()));
+    }
+
+    #[test]
+    fn test_travel_advances_time_and_rolls() {
+        let mut w = Wayline::default();
+        submit(&mut w, "travel 30");
+        assert_eq!(w.current_time_minutes(), 30);
+        assert_eq!(last_line(&w), "No table selected, so no encounter roll.");
+
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "use monsters");
+        submit(&mut w, "travel 60");
+        assert_eq!(w.current_time_minutes(), 90);
+        assert_eq!(
+            last_lines(&w, 2),
+            ["Added 60 minutes. New time: 01:30 (night)", "Monsters -> (1): rolled: Goblin"]
+        );
+
+        submit(&mut w, "travel 10 1d1>1");
+        assert_eq!(w.current_time_minutes(), 100);
+        assert_eq!(last_line(&w), "Check 1d1>1: rolled 1, failed.");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_edit_file_reports_editor_failures() {
+        let edit = |editor: &str| {
+            iced::futures::executor::block_on(edit_file(editor.to_string(), CONFIG_PATH.into()))
+        };
+        assert_eq!(edit("true"), Ok(()));
+        assert_eq!(
+            edit("false --wait"),
+            Err("Editor 'false --wait' exited with exit status: 1; not reloading.".to_string())
+        );
+        assert_eq!(
+            edit("wayline-no-such-editor"),
+            Err("Editor 'wayline-no-such-editor' not found; set $EDITOR to one that's installed."
+                .to_string())
+        );
+    }
+
+    #[test]
+    fn test_roll_log_has_only_rolls() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "roll monsters");
+        submit(&mut w, "time");
+        submit(&mut w, "dice 1d1");
+        submit(&mut w, "roll nothing");
+        assert_eq!(w.roll_log, ["Monsters -> (1): rolled: Goblin", "Rolled 1d1: 1"]);
+        assert_eq!(w.roll_log_content.text().trim_end(), w.roll_log.join("\n"));
+
+        submit(&mut w, "log");
+        assert!(w.roll_log_hidden);
+        assert_eq!(last_line(&w), "Roll log hidden.");
+        submit(&mut w, "log");
+        assert!(!w.roll_log_hidden);
+    }
+
+    #[test]
+    fn test_find_table_by_dice() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Reactions"
+            dice = "2d6"
+            rows = []
+            [[table]]
+            name = "Monsters"
+            dice = "d20"
+            rows = []
+            [[table]]
+            name = "Weather"
+            dice = "2D6"
+            rows = []
+            "#,
+        );
+        submit(&mut w, "findtable 2d6");
+        assert_eq!(last_line(&w), "Tables rolled on 2d6: Reactions, Weather.");
+        submit(&mut w, "findtable 1d20");
+        assert_eq!(last_line(&w), "Tables rolled on 1d20: Monsters.");
+        submit(&mut w, "findtable d8");
+        assert_eq!(last_line(&w), "No tables are rolled on d8.");
+        submit(&mut w, "findtable");
+        assert_eq!(last_lines(&w, 2), ["1d20: Monsters", "2d6: Reactions, Weather"]);
+    }
+
+    #[test]
+    fn test_log_file_gets_every_line() {
+        let path =
+            std::env::temp_dir().join(format!("wayline-log-file-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut w = Wayline::default();
+        w.open_transcript(path);
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "roll monsters");
+        submit(&mut w, "nonsense");
+        let written = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let lines: Vec<&str> = w.scrollback.iter().map(|line| line.text.as_str()).collect();
+        assert_eq!(written.lines().collect::<Vec<_>>(), lines);
+
+        let mut w = Wayline::default();
+        w.open_transcript("/nonexistent-dir/wayline.log");
+        assert!(w.transcript.is_none());
+        assert!(last_line(&w).ends_with("output won't be saved."));
+    }
+
+    #[test]
+    fn test_peek_leaves_no_trace() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "dice 1d1");
+        submit(&mut w, "peek monsters");
+        assert_eq!(last_line(&w), "(peek) Monsters -> (1): rolled: Goblin");
+        assert_eq!(w.last_result.as_deref(), Some("Rolled 1d1: 1"));
+        assert_eq!(w.roll_log, ["Rolled 1d1: 1"]);
+        submit(&mut w, "peek");
+        assert_eq!(last_line(&w), "No table selected.");
+    }
+
+    #[test]
+    fn test_window_settings() {
+        let mut w = Wayline::default();
+        assert_eq!(w.title(), "wayline");
+        assert_eq!(w.theme(), iced::Theme::Ferra);
+
+        w.load_all("[window]\ntitle = \"Thracia\"\ntheme = \"tokyo-night\"");
+        assert_eq!(w.title(), "Thracia");
+        assert_eq!(w.theme(), iced::Theme::TokyoNight);
+
+        w.load_all("[window]\ntheme = \"Plaid\"");
+        assert_eq!(w.title(), "wayline");
+        assert_eq!(w.theme(), iced::Theme::Ferra);
+        assert_eq!(last_line(&w), "Unknown theme 'Plaid', using Ferra.");
+    }
+
+    #[test]
+    fn test_compare_tables() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d4"
+            [[table.rows]]
+            name = "Goblin"
+            numbers = [1, 2, 3]
+            [[table.rows]]
+            name = "Orc"
+            numbers = [4]
+            [[table]]
+            name = "Beasts"
+            dice = "weighted"
+            [[table.rows]]
+            name = "Wolf"
+            [[table.rows]]
+            name = "goblin"
+            "#,
+        );
+        submit(&mut w, "compare monsters beasts");
+        assert_eq!(
+            last_lines(&w, 4),
+            [
+                "Entry   Monsters (1d4)  Beasts (weighted)",
+                "Goblin           75.0%              50.0%",
+                "Orc              25.0%                  -",
+                "Wolf                 -              50.0%",
+            ]
+        );
+        submit(&mut w, "compare monsters dragons");
+        assert_eq!(last_line(&w), "Table 'dragons' not found.");
+    }
+
+    #[test]
+    fn test_recent_rolls_newest_first() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "rolls");
+        assert_eq!(last_line(&w), "No rolls yet.");
+
+        submit(&mut w, "roll monsters");
+        submit(&mut w, "dice 2d1");
+        submit(&mut w, "roll monsters with 2d1");
+        submit(&mut w, "rolls");
+        assert_eq!(
+            last_lines(&w, 3),
+            [
+                "1. Monsters (2): no matching entry",
+                "2. 2d1: 2",
+                "3. Monsters (1): Goblin",
+            ]
+        );
+
+        for _ in 0..MAX_RECENT_ROLLS {
+            submit(&mut w, "dice 1d1");
+        }
+        assert_eq!(w.recent_rolls.len(), MAX_RECENT_ROLLS);
+    }
+
+    #[test]
+    fn test_weights_become_ranges_on_dice_tables() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Encounters"
+            dice = "d100"
+            [[table.rows]]
+            name = "Goblins"
+            weight = 50
+            [[table.rows]]
+            name = "Orcs"
+            weight = 30
+            [[table.rows]]
+            name = "Dragon"
+            weight = 20
+            "#,
+        );
+        submit(&mut w, "list encounters");
+        assert_eq!(
+            last_lines(&w, 3),
+            ["- Goblins: [1-50]", "- Orcs: [51-80]", "- Dragon: [81-100]"]
+        );
+    }
+
+    #[test]
+    fn test_quiet_startup() {
+        let mut w = Wayline { quiet: true, ..Wayline::default() };
+        let _ = w.update(Message::WindowOpened);
+        let _ = w.update(Message::ConfigLoaded(Ok(TEST_TABLES.to_string())));
+        assert!(w.current_table.is_some());
+        assert!(w.scrollback.iter().all(|line| line.kind != LineKind::Info));
+
+        let mut w = Wayline::default();
+        let config = "[ui]\nquiet = true\n[[table]]\nname = \"Broken\"";
+        let _ = w.update(Message::ConfigLoaded(Ok(config.to_string())));
+        assert!(!w.scrollback.is_empty());
+        assert!(w.scrollback.iter().all(|line| line.kind == LineKind::Error));
+        assert_eq!(w.scrollback[0].text, "Loaded 0 tables from tables.toml, 1 failed:");
+
+        // Once started, info lines show again.
+        submit(&mut w, "log");
+        assert_eq!(last_line(&w), "Roll log hidden.");
+    }
+
+    #[test]
+    fn test_autoscroll_command() {
+        let mut w = Wayline::default();
+        w.load_settings("[ui]\nautoscroll = false");
+        assert!(w.autoscroll_off);
+        submit(&mut w, "autoscroll");
+        assert_eq!(last_line(&w), "Autoscroll on.");
+        assert!(!w.autoscroll_off);
+        submit(&mut w, "autoscroll");
+        assert_eq!(last_line(&w), "Autoscroll off.");
+
+        let lines = w.scrollback.len();
+        submit(&mut w, "top");
+        submit(&mut w, "bottom");
+        assert_eq!(w.scrollback.len(), lines + 2); // Just the echoes
+    }
+
+    #[test]
+    fn test_scroll_offset() {
+        use scrollable::RelativeOffset;
+        let mut w = Wayline::default();
+        // Anchored to the bottom, the top is the far end.
+        assert_eq!(w.scroll_offset(true), RelativeOffset::END);
+        assert_eq!(w.scroll_offset(false), RelativeOffset::START);
+        submit(&mut w, "autoscroll");
+        assert_eq!(w.scroll_offset(true), RelativeOffset::START);
+        assert_eq!(w.scroll_offset(false), RelativeOffset::END);
+    }
+
+    #[test]
+    fn test_average_command() {
+        let mut w = Wayline::default();
+        submit(&mut w, "avg 10 1d1");
+        assert_eq!(last_line(&w), "Average of 10 rolls of 1d1: 1.00 (min 1, max 1)");
+        submit(&mut w, "avg 1001 10000d6");
+        assert_eq!(
+            last_line(&w),
+            "Can't average 1001 rolls of 10000d6: that's more than 10000000 dice in all."
+        );
+    }
+
+    #[test]
+    fn test_oppose_command() {
+        let mut w = Wayline::default();
+        submit(&mut w, "oppose 3d1 vs 1d1+1");
+        assert_eq!(last_line(&w), "3d1 (3) vs 1d1+1 (2): left wins by 1");
+        submit(&mut w, "oppose 1d1 vs 2d1-1");
+        assert_eq!(last_line(&w), "1d1 (1) vs 2d1-1 (1): tie");
+        submit(&mut w, "oppose 1d1 vs goblins");
+        assert_eq!(
+            last_line(&w),
+            "Can't roll 1d1 vs goblins: each side must be dice, optionally with +N or -N."
+        );
+    }
+
+    #[test]
+    fn test_sample_command() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "sample 20 monsters");
+        assert_eq!(last_lines(&w, 2), ["Monsters, 20 rolls:", "- Goblin: 20 (100.0%)"]);
+    }
+
+    #[test]
+    fn test_roll_interpolates_dice_in_entry_names() {
+        let mut w = Wayline::default();
+        w.load_all(
+            r#"
+            [[table]]
+            name = "Monsters"
+            dice = "1d1"
+            [[table.rows]]
+            name = "{1d1} goblins and a {bugbear}"
+            numbers = [1]
+            "#,
+        );
+        submit(&mut w, "roll monsters");
+        assert_eq!(last_lines(&w, 2)[0], "Monsters -> (1): rolled: 1 goblins and a {bugbear}");
+        assert!(last_line(&w).starts_with("Warning: table 'Monsters': can't roll {bugbear} in"));
+    }
+
+    #[test]
+    fn test_roll_emits_event() {
+        let path = std::env::temp_dir()
+            .join(format!("wayline-events-{}.jsonl", std::process::id()));
+        let path = path.to_str().unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let mut w = Wayline {
+            event_log: Some(EventLog::open(path).unwrap()),
+            ..Wayline::default()
+        };
+        w.load_all(TEST_TABLES);
+        w.on_roll_command(Some("monsters".to_string()), None, Vec::new());
+
+        let logged = std::fs::read_to_string(path).unwrap();
+        let _ = std::fs::remove_file(path);
+        let event: serde_json::Value = serde_json::from_str(logged.trim()).unwrap();
+        assert_eq!(
+            event,
+            serde_json::json!({
+                "event": "RollMade",
+                "table": "Monsters",
+                "dice": "1d1",
+                "roll": 1,
+                "result": "Goblin",
+            })
+        );
+    }
+
+    #[test]
+    fn test_lookup_command() {
+        let mut w = Wayline::default();
+        w.on_lookup_command("goblin");
+        assert_eq!(last_line(&w), "No tables loaded.");
+
+        w.load_all(TEST_TABLES);
+        w.current_table = Some("Monsters".to_string());
+        w.on_lookup_command("goblin");
+        assert_eq!(
+            last_lines(&w, 2),
+            ["Monsters: Goblin -> [1]", "1d20: Goblin -> [1, 2]"]
+        );
+
+        w.on_lookup_command("dragon");
+        assert_eq!(last_line(&w), "No entry named 'dragon' found.");
+    }
+
+    fn big_table() -> table::Table {
+        let rows = (1..=25)
+            .map(|n| table::Entry {
+                name: format!("Entry {}", n),
+                numbers: vec![n],
+                ..Default::default()
+            })
+            .collect();
+        table::Table {
+            name: "Big".to_string(),
+            dice: "1d25".to_string(),
+            rows,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_table_info_range() {
+        let table = table::Table {
+            name: "Monsters".to_string(),
+            dice: "2d6".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(get_table_info(&table), ["Table: Monsters", "Range: 2–12 (2d6)"]);
+
+        let table = table::Table { dice: "d66".to_string(), ..table };
+        assert_eq!(get_table_info(&table)[1], "Range: 11–66 (d66)");
+        let table = table::Table { dice: "weighted".to_string(), ..table };
+        assert_eq!(get_table_info(&table)[1], "Dice: weighted");
+    }
+
+    #[test]
+    fn test_tables_shows_source_files() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        w.load_all_from(
+            Path::new("extra/gems.toml"),
+            "[[table]]\nname = \"Gems\"\ndice = \"1d1\"\nrows = []",
+        );
+        submit(&mut w, "tables");
+        assert_eq!(
+            last_lines(&w, 3),
+            ["1d20: tables.toml", "Gems: extra/gems.toml", "Monsters: tables.toml"]
+        );
+    }
+
+    #[test]
+    fn test_list_all_summary() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        submit(&mut w, "list all");
+        assert_eq!(
+            last_lines(&w, 3),
+            ["Loaded tables:", "1. 1d20 (1d1, 2 entries)", "2. Monsters (1d1, 1 entry)"]
+        );
+    }
+
+    #[test]
+    fn test_list_pagination() {
+        let mut w = Wayline::default();
+        w.tables.insert("big".to_string(), big_table());
+
+        // Two header lines plus 25 rows make 27 lines over two pages.
+        w.on_list_command(Some("big".to_string()));
+        assert_eq!(w.scrollback.len(), LIST_PAGE_SIZE + 1);
+        assert_eq!(last_line(&w), "page 1/2 — type 'more' to continue");
+
+        w.on_more_command();
+        assert_eq!(last_lines(&w, 2)[0], "- Entry 25: [25]");
+        assert_eq!(last_line(&w), "page 2/2");
+
+        w.on_more_command();
+        assert_eq!(last_line(&w), "Nothing more to list.");
+    }
+
+    #[test]
+    fn test_headless_list_is_not_paged() {
+        let mut w = Wayline {
+            headless: true,
+            ..Wayline::default()
+        };
+        w.tables.insert("big".to_string(), big_table());
+
+        let _ = w.dispatch(command::parse_command("list big"));
+        assert!(w.scrollback.is_empty());
+        assert!(w.pending_listing.is_empty());
+    }
+
+    #[test]
+    fn test_roll_prefers_table_named_like_dice() {
+        let mut w = Wayline::default();
+        w.load_all(TEST_TABLES);
+        w.on_roll_command(Some("1d20".to_string()), None, Vec::new());
+        assert_eq!(last_line(&w), "1d20 -> (1): rolled: Not Dice");
+    }
 }
@@ -0,0 +1,33 @@
+use iced::widget::text;
+use iced::{Theme, color};
+
+// What a line of output is, so the scrollback can style it. The text itself stays plain.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    Info,
+    Error,
+    Result,
+    Echo, // the user's own input
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScrollbackLine {
+    pub kind: LineKind,
+    pub text: String,
+}
+
+impl LineKind {
+    pub fn style(self, theme: &Theme) -> text::Style {
+        match self {
+            LineKind::Info => text::Style {
+                color: Some(color!(0xEEEEEE)),
+            },
+            LineKind::Echo => text::Style {
+                color: Some(color!(0xAAAAAA)),
+            },
+            LineKind::Result => text::success(theme),
+            LineKind::Error => text::danger(theme),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+// The bits of a session worth keeping between runs: where the party is in game time and
+// which table was in use. Everything else is rebuilt from the config on startup.
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    pub current_table: Option<String>,
+    pub current_time_minutes: u32, // Days are counted in it, from day 1 at 0
+}
+
+pub fn load(path: &str) -> Result<SessionState, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read session file {}: {}", path, e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse session file {}: {}", path, e))
+}
+
+pub fn save(path: &str, state: &SessionState) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize session: {}", e))?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write session file {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_round_trip() {
+        let state = SessionState {
+            current_table: Some("wilderness encounters".to_string()),
+            current_time_minutes: 135,
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: SessionState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, state);
+    }
+}
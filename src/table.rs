@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+
 use serde::{Deserialize, Serialize};
 
 // We want to have tables that can be serialized/deserialized to/from TOML.
@@ -22,21 +25,381 @@ use serde::{Deserialize, Serialize};
 // result = [12, 12]
 // ```
 //
+// A table can instead set `dice = "weighted"` and give its rows a `weight` rather than
+// `numbers`; each row is then picked with probability proportional to its weight (default 1).
+//
+// A row can name a `subtable` to roll on next, e.g. `subtable = "Goblin Gear"`; `gen` follows
+// the chain.
+//
+// A table with several results per roll lists its `columns = ["terrain", "weather"]`, and each
+// row gives its value for them, e.g. `columns = { terrain = "Forest", weather = "Rain" }`.
+// `roll <table>.<column>` shows just one of them.
+//
+// `[table.modifiers]` names adjustments to the roll, e.g. `night = 1`, which `roll <table>
+// :night` adds before looking up the result.
+//
+// `quantity = "2d4"` on a row says how many appear, rolled along with it; set on the table,
+// it applies to every row without its own.
+//
+// `reroll_on = [2]` makes `roll` roll again, once, whenever the dice come up 2.
+//
+// `[table.aliases]`, e.g. `n = "roll"`, are command shortcuts that only work while the table
+// is current, taking priority over the config's `[aliases]`.
+//
+// Entry names can contain dice in braces, rolled each time the entry comes up, e.g.
+// `name = "{2d4} goblins"`.
+//
+// Instead of (or as well as) listing `numbers`, a row can give `min` and/or `max` to cover
+// every result in between, e.g. `min = 10` for "10 or higher". On a dice table, rows that give
+// only a `weight` are handed consecutive runs of results that long, e.g. 50/30/20 on d100.
+//
+// A row with `enabled = false` is still listed but never rolled, as if its numbers were
+// uncovered, e.g. to try a table without it.
+//
+// Any other keys on a row, e.g. `cr = "1/4"` or `xp = 50`, are kept as details and shown when
+// the row is rolled.
+//
+// `see = "Goblin Ambush"` on a row borrows the details of the row with that name in the same
+// table, for any keys it doesn't give itself.
+//
+// Rows normally can't share results: the first one listed wins. A table with `priority = true`
+// lets them nest instead, e.g. "12: special; 10-12: rare; otherwise common". A roll then gives
+// the matching row with the highest `priority` (default 0), and among those the one covering
+// the fewest results, then the first listed.
+//
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Table {
     pub name: String,
     pub rows: Vec<Entry>,
     pub dice: String, // e.g., "2d6",
+    #[serde(default)]
+    pub description: Option<String>, // One-line summary shown by `use` and `list`
+    #[serde(default)]
+    pub fallback: Option<String>, // Result for rolls no row covers
+    #[serde(default)]
+    pub quantity: Option<String>, // Dice for how many appear, for rows without their own
+    #[serde(default)]
+    pub reroll_on: Vec<u32>, // Results rerolled once, e.g. `[1]` to reroll the lowest
+    #[serde(default)]
+    pub priority: bool, // Rows may overlap; the most specific (or highest priority) wins
+
+    // Attribution, shown by `list` when present
+    #[serde(default)]
+    pub author: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub edition: Option<String>,
+
+    // Names of the results each row gives per roll, in display order
+    #[serde(default)]
+    pub columns: Vec<String>,
+
+    // Named adjustments to the roll, e.g. `night = 1`, applied with `roll <table> :night`
+    #[serde(default)]
+    pub modifiers: HashMap<String, i32>,
+
+    // Command shortcuts while this is the current table, e.g. `n = "roll"`
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+}
+
+impl Table {
+    /// The value of the modifier called `name`, which matches ignoring case.
+    pub fn modifier(&self, name: &str) -> Option<(&str, i32)> {
+        self.modifiers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(key, value)| (key.as_str(), *value))
+    }
+
+    /// The dice for how many of `entry` appear: its own `quantity`, or else the table's.
+    pub fn quantity_for<'a>(&'a self, entry: &'a Entry) -> Option<&'a str> {
+        entry.quantity.as_deref().or(self.quantity.as_deref())
+    }
+
+    /// The row called `name`, which matches ignoring case.
+    pub fn entry_named(&self, name: &str) -> Option<&Entry> {
+        self.rows.iter().find(|entry| entry.name.eq_ignore_ascii_case(name))
+    }
+
+    /// `entry`'s details along with those of the rows it `see`s, in turn; a row's own details
+    /// win. Stops at a row already visited, so rows that see each other don't loop.
+    pub fn details_for<'a>(&'a self, entry: &'a Entry) -> HashMap<&'a str, &'a toml::Value> {
+        let mut details = HashMap::new();
+        let mut visited: Vec<&str> = Vec::new();
+        let mut current = Some(entry);
+        while let Some(entry) = current
+            && !visited.iter().any(|name| name.eq_ignore_ascii_case(&entry.name))
+        {
+            visited.push(&entry.name);
+            for (key, value) in &entry.details {
+                details.entry(key.as_str()).or_insert(value);
+            }
+            current = entry.see.as_deref().and_then(|name| self.entry_named(name));
+        }
+        details
+    }
+
+    /// The table's name for `column`, which matches ignoring case.
+    pub fn column(&self, column: &str) -> Option<&str> {
+        self.columns
+            .iter()
+            .find(|name| name.eq_ignore_ascii_case(column))
+            .map(String::as_str)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Entry {
     pub name: String,
+    #[serde(default)]
     pub numbers: Vec<u32>, // Die results that correspond to this entry
+    #[serde(default)]
+    pub min: Option<u32>, // With `max`, an inclusive range of results, e.g. `min = 10` for 10+
+    #[serde(default)]
+    pub max: Option<u32>,
+    #[serde(default)]
+    pub weight: Option<u32>, // Relative chance of this entry in a weighted table
+    #[serde(default)]
+    pub subtable: Option<String>, // Table `gen` rolls next when this entry comes up
+    #[serde(default)]
+    pub see: Option<String>, // Row in the same table whose details this one shares
+    #[serde(default)]
+    pub priority: Option<i32>, // On a `priority` table, beats overlapping rows with less
+    #[serde(default)]
+    pub quantity: Option<String>, // Dice for how many appear, e.g. "2d4"
+    #[serde(default)]
+    pub columns: HashMap<String, String>, // Value for each of the table's columns
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool, // Disabled rows are listed but never rolled
+    #[serde(flatten)]
+    pub details: HashMap<String, toml::Value>, // Keys not covered by the fields above
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl Entry {
+    /// The results from `min` to `max`, if either is set; the other end is open.
+    pub fn range(&self) -> Option<RangeInclusive<u32>> {
+        if self.min.is_none() && self.max.is_none() {
+            return None;
+        }
+        Some(self.min.unwrap_or(0)..=self.max.unwrap_or(u32::MAX))
+    }
+
+    /// How many results the entry covers, between its `numbers` and its range; an open range
+    /// counts up to `u32::MAX`.
+    pub fn span(&self) -> u64 {
+        let range = self.range().map_or(0, |range| {
+            (*range.end() as u64 + 1).saturating_sub(*range.start() as u64)
+        });
+        self.numbers.len() as u64 + range
+    }
+
+    /// Whether rolling `total` gives this entry, by its `numbers` or its range.
+    pub fn covers(&self, total: u32) -> bool {
+        self.numbers.contains(&total) || self.range().is_some_and(|range| range.contains(&total))
+    }
+}
+
+// Written out rather than derived so that entries built in code are enabled, like parsed ones.
+impl Default for Entry {
+    fn default() -> Self {
+        Entry {
+            name: String::new(),
+            numbers: Vec::new(),
+            min: None,
+            max: None,
+            weight: None,
+            subtable: None,
+            see: None,
+            priority: None,
+            quantity: None,
+            columns: HashMap::new(),
+            enabled: true,
+            details: HashMap::new(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TableList {
     pub table: Vec<Table>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_details() {
+        let entry: Entry = toml::from_str(
+            r#"
+            name = "Goblin"
+            numbers = [1, 2]
+            weight = 3
+            cr = "1/4"
+            xp = 50
+            "#,
+        )
+        .unwrap();
+        assert_eq!(entry.numbers, [1, 2]);
+        assert_eq!(entry.weight, Some(3));
+        assert!(entry.enabled);
+        assert_eq!(entry.details.len(), 2);
+        assert_eq!(entry.details["cr"].as_str(), Some("1/4"));
+        assert_eq!(entry.details["xp"].as_integer(), Some(50));
+    }
+
+    #[test]
+    fn test_entry_see() {
+        let table: Table = toml::from_str(
+            r#"
+            name = "Encounters"
+            dice = "1d6"
+            [[rows]]
+            name = "Goblin Ambush"
+            cr = "1/4"
+            xp = 50
+            see = "Goblin Lair"
+            [[rows]]
+            name = "Goblin Scouts"
+            see = "goblin ambush"
+            xp = 25
+            [[rows]]
+            name = "Goblin Lair"
+            lair = "cave"
+            see = "Goblin Scouts"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(table.rows[1].see.as_deref(), Some("goblin ambush"));
+        assert_eq!(table.rows[1].details.len(), 1);
+        assert_eq!(table.rows[0].see.as_deref(), Some("Goblin Lair"));
+        assert_eq!(Entry::default().see, None);
+
+        let details = table.details_for(&table.rows[1]);
+        assert_eq!(details.len(), 3);
+        assert_eq!(details["xp"].as_integer(), Some(25));
+        assert_eq!(details["cr"].as_str(), Some("1/4"));
+        assert_eq!(details["lair"].as_str(), Some("cave"));
+        assert_eq!(table.details_for(&table.rows[2]).len(), 3);
+    }
+
+    #[test]
+    fn test_entry_enabled() {
+        let entry: Entry = toml::from_str("name = \"Goblin\"\nenabled = false").unwrap();
+        assert!(!entry.enabled);
+        assert!(entry.details.is_empty());
+        assert!(Entry::default().enabled);
+    }
+
+    #[test]
+    fn test_entry_min() {
+        let entry: Entry = toml::from_str("name = \"Dragon\"\nmin = 10").unwrap();
+        assert_eq!(entry.min, Some(10));
+        assert_eq!(entry.max, None);
+        assert!(entry.details.is_empty());
+        assert!(!entry.covers(9));
+        assert!(entry.covers(10));
+        assert!(entry.covers(u32::MAX));
+        assert_eq!(Entry::default().range(), None);
+    }
+
+    #[test]
+    fn test_table_columns() {
+        let table: Table = toml::from_str(
+            r#"
+            name = "Hexes"
+            dice = "1d6"
+            columns = ["terrain", "weather"]
+            [[rows]]
+            name = "Wet Woods"
+            numbers = [1]
+            columns = { terrain = "Forest", weather = "Rain" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(table.columns, ["terrain", "weather"]);
+        assert_eq!(table.column("Weather"), Some("weather"));
+        assert_eq!(table.column("wind"), None);
+        assert_eq!(table.rows[0].columns["weather"], "Rain");
+        assert!(table.rows[0].details.is_empty());
+        assert_eq!(table.fallback, None);
+    }
+
+    #[test]
+    fn test_table_modifiers() {
+        let table: Table = toml::from_str(
+            r#"
+            name = "Monsters"
+            dice = "2d6"
+            rows = []
+            [modifiers]
+            night = 1
+            Daylight = -2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(table.modifiers.len(), 2);
+        assert_eq!(table.modifier("NIGHT"), Some(("night", 1)));
+        assert_eq!(table.modifier("daylight"), Some(("Daylight", -2)));
+        assert_eq!(table.modifier("rain"), None);
+    }
+
+    #[test]
+    fn test_table_aliases() {
+        let table: Table = toml::from_str(
+            "name = \"Hexes\"\ndice = \"1d6\"\nrows = []\n[aliases]\nn = \"roll\"",
+        )
+        .unwrap();
+        assert_eq!(table.aliases["n"], "roll");
+        let table: Table = toml::from_str("name = \"Hexes\"\ndice = \"1d6\"\nrows = []").unwrap();
+        assert!(table.aliases.is_empty());
+    }
+
+    #[test]
+    fn test_quantity_falls_back_to_table() {
+        let table: Table = toml::from_str(
+            r#"
+            name = "Monsters"
+            dice = "1d6"
+            quantity = "2d4"
+            [[rows]]
+            name = "Goblin"
+            [[rows]]
+            name = "Dragon"
+            quantity = "1"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(table.quantity_for(&table.rows[0]), Some("2d4"));
+        assert_eq!(table.quantity_for(&table.rows[1]), Some("1"));
+        assert!(table.rows[1].details.is_empty());
+
+        let table = Table { quantity: None, ..table };
+        assert_eq!(table.quantity_for(&table.rows[0]), None);
+    }
+
+    #[test]
+    fn test_table_reroll_on() {
+        let table: Table =
+            toml::from_str("name = \"Hexes\"\ndice = \"2d6\"\nrows = []\nreroll_on = [2, 12]")
+                .unwrap();
+        assert_eq!(table.reroll_on, [2, 12]);
+        let table: Table = toml::from_str("name = \"Hexes\"\ndice = \"2d6\"\nrows = []").unwrap();
+        assert!(table.reroll_on.is_empty());
+    }
+
+    #[test]
+    fn test_table_fallback() {
+        let table: Table =
+            toml::from_str("name = \"Hexes\"\ndice = \"1d6\"\nrows = []\nfallback = \"Nothing\"")
+                .unwrap();
+        assert_eq!(table.fallback.as_deref(), Some("Nothing"));
+    }
+}
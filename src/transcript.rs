@@ -0,0 +1,61 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+
+// A plain-text copy of the scrollback, appended to as each line is shown, for keeping a record
+// of a whole campaign. Written when `--log-file <path>` is given.
+
+/// Appends lines of output to a file.
+pub struct Transcript {
+    writer: BufWriter<File>,
+}
+
+impl std::fmt::Debug for Transcript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transcript").finish_non_exhaustive()
+    }
+}
+
+impl Transcript {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open log file {}: {}", path, e))?;
+        Ok(Transcript {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn write_line(&mut self, line: &str) -> Result<(), String> {
+        // Flushed per line so the file is complete even if the app is killed.
+        writeln!(self.writer, "{}", line)
+            .and_then(|_| self.writer.flush())
+            .map_err(|e| format!("Failed to write to log file: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lines_are_appended_in_order() {
+        let path = std::env::temp_dir()
+            .join(format!("wayline-transcript-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "earlier session\n").unwrap();
+
+        let mut transcript = Transcript::open(path).unwrap();
+        for line in ["> roll", "Monsters -> (3): rolled: Goblin", "> time"] {
+            transcript.write_line(line).unwrap();
+        }
+        let written = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            written,
+            "earlier session\n> roll\nMonsters -> (3): rolled: Goblin\n> time\n"
+        );
+    }
+}
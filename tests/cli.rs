@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const GAPPY_TABLES: &str = r#"
+    [[table]]
+    name = "Gappy"
+    dice = "1d6"
+    [[table.rows]]
+    name = "Goblin"
+    numbers = [1, 2]
+"#;
+
+/// Writes `contents` to a file named `name` in a fresh temporary directory.
+fn write_temp(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wayline-cli-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn wayline(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wayline")).args(args).output().unwrap()
+}
+
+#[test]
+fn test_roll_json_output_is_only_json() {
+    let path = write_temp("json.toml", GAPPY_TABLES);
+    let output = wayline(&["--config", path.to_str().unwrap(), "roll", "gappy", "--json"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(json["table"], "Gappy");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Warning: table 'Gappy': no entry covers 3-6."), "{}", stderr);
+
+    let output =
+        wayline(&["--quiet", "--config", path.to_str().unwrap(), "roll", "gappy", "--json"]);
+    assert!(String::from_utf8(output.stderr).unwrap().is_empty());
+}
+
+#[test]
+fn test_failed_command_exits_with_failure() {
+    let path = write_temp("exit.toml", GAPPY_TABLES);
+    let config = path.to_str().unwrap();
+    assert!(wayline(&["--config", config, "roll", "gappy"]).status.success());
+    let output = wayline(&["--config", config, "roll", "nosuch"]);
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Table 'nosuch' not found.\n");
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_check_lists_each_problem_once() {
+    let path = write_temp("check.toml", GAPPY_TABLES);
+    let output = wayline(&["--check", path.to_str().unwrap()]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            format!("{}: table 'Gappy': no entry covers 3-6", path.display()),
+            "1 problem found.".to_string(),
+        ]
+    );
+    assert!(!output.status.success());
+}
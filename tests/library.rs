@@ -0,0 +1,26 @@
+use wayline::command::Command;
+use wayline::{parse_command, parse_tables, roll, roll_on};
+
+const TABLES: &str = r#"
+    [[table]]
+    name = "Monsters"
+    dice = "2d6"
+    [[table.rows]]
+    name = "Goblin"
+    numbers = [2, 3, 4, 5, 6]
+    [[table.rows]]
+    name = "Ogre"
+    numbers = [7, 8, 9, 10, 11, 12]
+"#;
+
+#[test]
+fn test_roll_on_parsed_table() {
+    let tables = parse_tables(TABLES).unwrap();
+    let (total, rolls, entry) = roll_on(&tables[0], &tables[0].dice);
+    assert!((2..=12).contains(&total));
+    assert_eq!(rolls.len(), 2);
+    assert!(entry.is_some());
+
+    assert!(roll("3d6").is_some_and(|total| (3..=18).contains(&total)));
+    assert_eq!(parse_command("roll monsters"), Command::RollTable(Some("monsters".to_string()), None, vec![]));
+}